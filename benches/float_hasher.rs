@@ -0,0 +1,61 @@
+//! Compares the `FloatBuildHasher` against std's default SipHash on the kind of workload in
+//! `examples/hashmap.rs`: grouping many float keys, then looking each of them up again.
+//!
+//! Run with `cargo bench --bench float_hasher`.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use total_float_wrap::{FloatBuildHasher, TotalF64};
+
+const SIDE: u32 = 300;
+const ROUNDS: u32 = 20;
+
+fn angles() -> Vec<TotalF64> {
+    (1..=SIDE)
+        .flat_map(|adjacent| {
+            (1..=SIDE).map(move |opposite| f64::atan2(adjacent.into(), opposite.into()).into())
+        })
+        .collect()
+}
+
+fn group_and_lookup<S: BuildHasher + Default>(keys: &[TotalF64]) -> usize {
+    let mut groups: HashMap<TotalF64, u32, S> = HashMap::default();
+    for &key in keys {
+        *groups.entry(key).or_default() += 1;
+    }
+
+    keys.iter().filter(|key| groups[key] > 1).count()
+}
+
+fn bench<S: BuildHasher + Default>(name: &str, keys: &[TotalF64]) -> Duration {
+    // warm up once before timing
+    black_box(group_and_lookup::<S>(keys));
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(group_and_lookup::<S>(black_box(keys)));
+    }
+    let elapsed = start.elapsed() / ROUNDS;
+
+    println!(
+        "{name:>16}: {elapsed:>10.2?} per round, {:>6.2} ns per key",
+        elapsed.as_nanos() as f64 / keys.len() as f64
+    );
+    elapsed
+}
+
+fn main() {
+    let keys = angles();
+    println!("{} keys, {ROUNDS} rounds", keys.len());
+
+    let sip = bench::<RandomState>("RandomState", &keys);
+    let float = bench::<FloatBuildHasher>("FloatBuildHasher", &keys);
+    println!(
+        "FloatBuildHasher is {:.2}x the speed of RandomState",
+        sip.as_secs_f64() / float.as_secs_f64()
+    );
+}
@@ -0,0 +1,26 @@
+use total_float_wrap::{FloatGroups, TotalF64};
+
+fn main() {
+    let start_adj = 1;
+    let end_adj = 10;
+    let start_opp = 1;
+    let end_opp = 30;
+
+    let points = (start_adj..=end_adj)
+        .flat_map(|adjacent| (start_opp..=end_opp).map(move |opposite| (adjacent, opposite)));
+    let triangles = FloatGroups::group_by_float(points, |&(adjacent, opposite): &(u32, u32)| {
+        f64::atan2(adjacent.into(), opposite.into())
+    });
+
+    println!(
+        "For the triangles in the square of points [{start_adj}..{end_adj}]x[{start_opp}..{end_opp}]"
+    );
+    for (TotalF64(angle), group) in triangles.largest_groups() {
+        println!("The group {group:?} has the maximal members");
+        println!(
+            "- with an angle of {:.2}° - a ratio of {:.5} between the opposite and the adjacent.",
+            angle.to_degrees(),
+            angle.tan()
+        );
+    }
+}
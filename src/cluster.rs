@@ -0,0 +1,203 @@
+use core::ops::Range;
+
+use crate::TotalF64;
+
+/// A run of values in a sorted slice whose neighbouring gaps are all within the tolerance.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cluster {
+    /// The indices of the values in the sorted slice
+    pub range: Range<usize>,
+    /// The least value in the total order
+    pub min: TotalF64,
+    /// The greatest value in the total order
+    pub max: TotalF64,
+    /// The arithmetic mean of the values
+    pub mean: TotalF64,
+    /// The middle value, the lower of the two middle values when there are an even number
+    pub median: TotalF64,
+}
+
+/// Sorts the values by the total order and clusters values at most `ulps` apart.
+///
+/// The distance between two values is the number of floats between them in the total order,
+/// so `0.0` and `-0.0` are one ulp apart, and NaNs with adjacent payloads are one ulp apart.
+/// Clustering is transitive, so a cluster can span more than `ulps` if its values are close
+/// together.
+///
+/// ```rust
+/// use total_float_wrap::{cluster_by_ulps, TotalF64};
+///
+/// let mut values = [0.3, 1.0, 0.1 + 0.2, 1.0 + f64::EPSILON];
+/// let clusters = cluster_by_ulps(&mut values, 1);
+///
+/// assert_eq!(clusters.len(), 2);
+/// assert_eq!(clusters[0].range, 0..2);
+/// assert_eq!(clusters[1].min, TotalF64(1.0));
+/// ```
+pub fn cluster_by_ulps<T: Copy + Into<TotalF64>>(values: &mut [T], ulps: u64) -> Vec<Cluster> {
+    cluster_by_ulps_by_key(values, |&v| v, ulps)
+}
+
+/// Sorts the items by the total order of their keys and clusters items whose keys are at most
+/// `ulps` apart, as in [`cluster_by_ulps`].
+pub fn cluster_by_ulps_by_key<T, K: Into<TotalF64>>(
+    items: &mut [T],
+    key: impl FnMut(&T) -> K,
+    ulps: u64,
+) -> Vec<Cluster> {
+    clusters(items, key, |a, b| {
+        ordered(b).wrapping_sub(ordered(a)) <= ulps
+    })
+}
+
+/// Sorts the values by the total order and clusters values at most `tolerance` apart.
+///
+/// Equal values in the total order are always clustered, so identical NaNs and infinities
+/// cluster together, while other NaNs are never within the tolerance of anything.
+/// `0.0` and `-0.0` are zero apart.
+///
+/// Panics if `tolerance` is negative or NaN.
+///
+/// ```rust
+/// use total_float_wrap::cluster_by_tolerance;
+///
+/// let mut readings = [10.02, 9.98, 20.0, 10.0, 19.97, f64::NAN];
+/// let clusters = cluster_by_tolerance(&mut readings, 0.05);
+///
+/// let means: Vec<f64> = clusters.iter().map(|c| c.mean.0).collect();
+/// assert_eq!(means.len(), 3);
+/// assert!((means[0] - 10.0).abs() < 1e-9);
+/// assert_eq!(clusters[1].median.0, 19.97);
+/// assert!(means[2].is_nan());
+/// ```
+pub fn cluster_by_tolerance<T: Copy + Into<TotalF64>>(
+    values: &mut [T],
+    tolerance: f64,
+) -> Vec<Cluster> {
+    cluster_by_tolerance_by_key(values, |&v| v, tolerance)
+}
+
+/// Sorts the items by the total order of their keys and clusters items whose keys are at most
+/// `tolerance` apart, as in [`cluster_by_tolerance`].
+pub fn cluster_by_tolerance_by_key<T, K: Into<TotalF64>>(
+    items: &mut [T],
+    key: impl FnMut(&T) -> K,
+    tolerance: f64,
+) -> Vec<Cluster> {
+    assert!(tolerance >= 0.0, "invalid tolerance {tolerance}");
+    clusters(items, key, |a, b| a == b || b.0 - a.0 <= tolerance)
+}
+
+/// Returns the position of the value in the total order, as an unsigned integer
+fn ordered(value: TotalF64) -> u64 {
+    value.normalise() as u64 ^ (1 << 63)
+}
+
+/// Sorts the items by key, then splits them wherever `close` is false for neighbouring keys
+fn clusters<T, K: Into<TotalF64>>(
+    items: &mut [T],
+    mut key: impl FnMut(&T) -> K,
+    close: impl Fn(TotalF64, TotalF64) -> bool,
+) -> Vec<Cluster> {
+    items.sort_by_cached_key(|item| key(item).into());
+    let keys: Vec<TotalF64> = items.iter().map(|item| key(item).into()).collect();
+
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    for end in 1..=keys.len() {
+        if end == keys.len() || !close(keys[end - 1], keys[end]) {
+            clusters.push(summarise(&keys[start..end], start));
+            start = end;
+        }
+    }
+    clusters
+}
+
+/// Computes the representatives of a non-empty sorted run of keys starting at `start`
+fn summarise(keys: &[TotalF64], start: usize) -> Cluster {
+    let n = keys.len() as f64;
+    let mut mean = keys.iter().map(|k| k.0).sum::<f64>() / n;
+    if mean.is_infinite() && keys.iter().all(|k| k.0.is_finite()) {
+        // the sum overflowed, so scale the values down first
+        mean = keys.iter().map(|k| k.0 / n).sum();
+    }
+
+    Cluster {
+        range: start..start + keys.len(),
+        min: keys[0],
+        max: keys[keys.len() - 1],
+        mean: TotalF64(mean),
+        median: keys[(keys.len() - 1) / 2],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_by_ulps_is_exact() {
+        let one = 1.0_f64;
+        let mut values: Vec<TotalF64> = [0, 3, 1, 7, 10]
+            .iter()
+            .map(|&ulps| TotalF64(f64::from_bits(one.to_bits() + ulps)))
+            .collect();
+
+        let ranges: Vec<Range<usize>> = cluster_by_ulps(&mut values, 2)
+            .into_iter()
+            .map(|c| c.range)
+            .collect();
+        assert_eq!(ranges, [0..3, 3..4, 4..5]);
+        assert_eq!(cluster_by_ulps(&mut values, 4).len(), 1);
+        assert_eq!(cluster_by_ulps(&mut values, 0).len(), 5);
+
+        // the sign change between the zeros is a single step
+        let mut zeros = [0.0, f64::from_bits(1), -0.0, -f64::from_bits(1)];
+        let clusters = cluster_by_ulps(&mut zeros, 1);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].median.0.to_bits(), (-0.0_f64).to_bits());
+
+        let mut extremes = [f64::MAX, f64::INFINITY, f64::NAN];
+        assert_eq!(cluster_by_ulps(&mut extremes, 1).len(), 2);
+        assert!(cluster_by_ulps::<f64>(&mut [], 1).is_empty());
+    }
+
+    #[test]
+    fn test_cluster_by_tolerance() {
+        let mut values = [
+            3.0,
+            1.0,
+            1.4,
+            1.8,
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::MAX,
+            f64::MAX,
+        ];
+        let clusters = cluster_by_tolerance(&mut values, 0.5);
+
+        assert_eq!(values[..4], [1.0, 1.4, 1.8, 3.0]);
+        assert_eq!(clusters.len(), 4);
+        assert_eq!(clusters[0].range, 0..3);
+        assert_eq!(clusters[0].max, TotalF64(1.8));
+        assert_eq!(clusters[0].median, TotalF64(1.4));
+        assert_eq!(clusters[2].mean, TotalF64(f64::MAX));
+        assert_eq!(clusters[3].mean, TotalF64(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_cluster_by_key() {
+        let mut readings = [("b", 2.0), ("a", 1.0), ("c", 1.1), ("d", 5.0)];
+        let clusters = cluster_by_tolerance_by_key(&mut readings, |r| r.1, 0.2);
+
+        assert_eq!(clusters.len(), 3);
+        let names: Vec<&str> = readings[clusters[0].range.clone()]
+            .iter()
+            .map(|r| r.0)
+            .collect();
+        assert_eq!(names, ["a", "c"]);
+
+        let clusters = cluster_by_ulps_by_key(&mut readings, |r| r.1 as f32, 0);
+        assert_eq!(clusters.len(), 4);
+    }
+}
@@ -0,0 +1,874 @@
+use core::fmt;
+
+use crate::float_index::partition;
+use crate::{TotalF32, TotalF64};
+
+/// Error returned when bytes cannot be decoded into a compressed set.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DecodeError {
+    /// The bytes end part way through the encoding.
+    Truncated,
+    /// The bytes are not a valid encoding.
+    Invalid,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DecodeError::Truncated => "encoding is truncated",
+            DecodeError::Invalid => "encoding is invalid",
+        })
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Maps a value to an unsigned integer with the same order, by flipping the normalised sign bit
+fn ordered_u64(value: TotalF64) -> u64 {
+    value.normalise() as u64 ^ (1 << 63)
+}
+
+fn from_ordered_u64(key: u64) -> TotalF64 {
+    TotalF64::from_normalised((key ^ (1 << 63)) as i64)
+}
+
+fn ordered_u32(value: TotalF32) -> u32 {
+    value.normalise() as u32 ^ (1 << 31)
+}
+
+fn from_ordered_u32(key: u32) -> TotalF32 {
+    TotalF32::from_normalised((key ^ (1 << 31)) as i32)
+}
+
+/// Merges two strictly increasing sequences, keeping the values in either
+fn union<T: Ord>(a: impl Iterator<Item = T>, b: impl Iterator<Item = T>) -> Vec<T> {
+    let (mut a, mut b) = (a.peekable(), b.peekable());
+    let mut out = Vec::new();
+    loop {
+        let next = match (a.peek(), b.peek()) {
+            (None, None) => break,
+            (Some(_), None) => a.next(),
+            (None, Some(_)) => b.next(),
+            (Some(x), Some(y)) if x < y => a.next(),
+            (Some(x), Some(y)) if x > y => b.next(),
+            _ => {
+                b.next();
+                a.next()
+            }
+        };
+        out.extend(next);
+    }
+    out
+}
+
+/// Merges two strictly increasing sequences, keeping the values in both
+fn intersection<T: Ord>(a: impl Iterator<Item = T>, b: impl Iterator<Item = T>) -> Vec<T> {
+    let (mut a, mut b) = (a.peekable(), b.peekable());
+    let mut out = Vec::new();
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        if x < y {
+            a.next();
+        } else if x > y {
+            b.next();
+        } else {
+            b.next();
+            out.extend(a.next());
+        }
+    }
+    out
+}
+
+/// Reads a little-endian encoding front to back
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if len > self.0.len() {
+            return Err(DecodeError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn usize(&mut self) -> Result<usize, DecodeError> {
+        usize::try_from(self.u64()?).map_err(|_| DecodeError::Invalid)
+    }
+
+    pub(crate) fn words(&mut self, count: usize) -> Result<Vec<u64>, DecodeError> {
+        let bytes = self.take(count.checked_mul(8).ok_or(DecodeError::Truncated)?)?;
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|w| u64::from_le_bytes(w.try_into().unwrap()))
+            .collect())
+    }
+
+    pub(crate) fn finish(self) -> Result<(), DecodeError> {
+        match self.0 {
+            [] => Ok(()),
+            _ => Err(DecodeError::Invalid),
+        }
+    }
+}
+
+/// Returns the position of the `k`th set bit of `word`, which must have more than `k` set bits
+fn select_in_word(mut word: u64, k: usize) -> usize {
+    for _ in 0..k {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+/// The number of words of the upper bits summarised by each entry of the select index
+const BLOCK_WORDS: usize = 8;
+
+/// An immutable set of `TotalF64`, compressed with Elias-Fano coding.
+///
+/// Each value is mapped to a `u64` with the same order, and split into low bits, which are
+/// stored verbatim, and high bits, which are stored in unary as gaps. A set of `n` values from a
+/// universe of `u` takes about `n * (2 + log2(u / n))` bits, so dense sets of nearby values, such
+/// as samples from a narrow range, compress very well.
+///
+/// Iteration follows the total order, and `rank` and `select` take logarithmic time.
+///
+/// ```rust
+/// use total_float_wrap::{CompressedF64Set, TotalF64};
+///
+/// let set: CompressedF64Set = (0..1000).map(|i| TotalF64(i as f64 / 8.0)).collect();
+///
+/// assert!(set.contains(TotalF64(12.5)));
+/// assert_eq!(set.rank(TotalF64(12.5)), 100);
+/// assert_eq!(set.select(100), Some(TotalF64(12.5)));
+///
+/// let bytes = set.to_bytes();
+/// assert_eq!(CompressedF64Set::from_bytes(&bytes), Ok(set));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompressedF64Set {
+    len: usize,
+    /// The smallest key, which every key is stored relative to
+    base: u64,
+    low_bits: u32,
+    /// The low bits of every value, packed back to back
+    lower: Vec<u64>,
+    /// A set bit for every value at `high + index`, so bucket `h` ends at the `h`th clear bit
+    upper: Vec<u64>,
+    upper_len: usize,
+    /// The number of set upper bits before each block of words
+    block_ones: Vec<usize>,
+}
+
+impl CompressedF64Set {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes strictly increasing ordered keys
+    fn from_sorted(keys: &[u64]) -> Self {
+        let (Some(&base), Some(&last)) = (keys.first(), keys.last()) else {
+            return Self::default();
+        };
+
+        let len = keys.len();
+        let max = last - base;
+        let low_bits = Self::low_bits_for(len, max);
+        let upper_len = len + (max >> low_bits) as usize + 1;
+
+        let mut lower = vec![0; (len * low_bits as usize).div_ceil(64)];
+        let mut upper = vec![0; upper_len.div_ceil(64)];
+        for (i, key) in keys.iter().map(|k| k - base).enumerate() {
+            if low_bits != 0 {
+                let low = key & ((1 << low_bits) - 1);
+                let bit = i * low_bits as usize;
+                lower[bit / 64] |= low << (bit % 64);
+                if bit % 64 + low_bits as usize > 64 {
+                    lower[bit / 64 + 1] |= low >> (64 - bit % 64);
+                }
+            }
+
+            let bit = (key >> low_bits) as usize + i;
+            upper[bit / 64] |= 1 << (bit % 64);
+        }
+
+        Self::with_index(len, base, low_bits, lower, upper, upper_len)
+    }
+
+    /// Chooses the number of low bits which minimises the size of the encoding, given the largest
+    /// key relative to the base
+    fn low_bits_for(len: usize, max: u64) -> u32 {
+        let universe = max as u128 + 1;
+        if universe > len as u128 {
+            (universe / len as u128).ilog2().min(63)
+        } else {
+            0
+        }
+    }
+
+    fn with_index(
+        len: usize,
+        base: u64,
+        low_bits: u32,
+        lower: Vec<u64>,
+        upper: Vec<u64>,
+        upper_len: usize,
+    ) -> Self {
+        let mut ones = 0;
+        let block_ones = upper
+            .chunks(BLOCK_WORDS)
+            .map(|block| {
+                let before = ones;
+                ones += block.iter().map(|w| w.count_ones() as usize).sum::<usize>();
+                before
+            })
+            .collect();
+
+        Self {
+            len,
+            base,
+            low_bits,
+            lower,
+            upper,
+            upper_len,
+            block_ones,
+        }
+    }
+
+    /// Returns the number of values in the set
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the set contains no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn low(&self, i: usize) -> u64 {
+        if self.low_bits == 0 {
+            return 0;
+        }
+
+        let bit = i * self.low_bits as usize;
+        let (word, offset) = (bit / 64, bit % 64);
+        let mut low = self.lower[word] >> offset;
+        if offset + self.low_bits as usize > 64 {
+            low |= self.lower[word + 1] << (64 - offset);
+        }
+        low & ((1 << self.low_bits) - 1)
+    }
+
+    /// Returns the position of the `k`th set bit of the upper bits
+    fn select1(&self, k: usize) -> usize {
+        let block = partition(0, self.block_ones.len(), |b| self.block_ones[b] <= k) - 1;
+        let mut k = k - self.block_ones[block];
+        for (w, &word) in self.upper.iter().enumerate().skip(block * BLOCK_WORDS) {
+            let ones = word.count_ones() as usize;
+            if k < ones {
+                return w * 64 + select_in_word(word, k);
+            }
+            k -= ones;
+        }
+        unreachable!("fewer than k set bits")
+    }
+
+    /// Returns the position of the `k`th clear bit of the upper bits
+    fn select0(&self, k: usize) -> usize {
+        let zeros_before = |b: usize| b * BLOCK_WORDS * 64 - self.block_ones[b];
+        let block = partition(0, self.block_ones.len(), |b| zeros_before(b) <= k) - 1;
+        let mut k = k - zeros_before(block);
+        for (w, &word) in self.upper.iter().enumerate().skip(block * BLOCK_WORDS) {
+            let zeros = word.count_zeros() as usize;
+            if k < zeros {
+                return w * 64 + select_in_word(!word, k);
+            }
+            k -= zeros;
+        }
+        unreachable!("fewer than k clear bits")
+    }
+
+    /// Returns the `i`th key relative to the base
+    fn offset(&self, i: usize) -> u64 {
+        let high = (self.select1(i) - i) as u64;
+        (high << self.low_bits) | self.low(i)
+    }
+
+    /// Returns the range of indices of the values whose high bits are `high`
+    fn bucket(&self, high: usize) -> core::ops::Range<usize> {
+        let start = match high {
+            0 => 0,
+            h => self.select0(h - 1) - (h - 1),
+        };
+        start..self.select0(high) - high
+    }
+
+    /// Returns the number of values in the set which are less than `value`
+    pub fn rank(&self, value: TotalF64) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+
+        let Some(key) = ordered_u64(value).checked_sub(self.base) else {
+            return 0;
+        };
+        let high = (key >> self.low_bits) as usize;
+        if high >= self.upper_len - self.len {
+            return self.len;
+        }
+
+        let bucket = self.bucket(high);
+        let low = key & ((1_u64 << self.low_bits) - 1);
+        let (mut lo, mut hi) = (bucket.start, bucket.end);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.low(mid) < low {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Returns the `k`th smallest value in the set, counting from zero
+    pub fn select(&self, k: usize) -> Option<TotalF64> {
+        (k < self.len).then(|| from_ordered_u64(self.base + self.offset(k)))
+    }
+
+    /// Returns whether the set contains the value
+    pub fn contains(&self, value: TotalF64) -> bool {
+        self.select(self.rank(value)) == Some(value)
+    }
+
+    fn offsets(&self) -> impl Iterator<Item = u64> + '_ {
+        let mut words = self.upper.iter().copied().enumerate();
+        let mut current = (0, 0);
+        (0..self.len).map(move |i| {
+            while current.1 == 0 {
+                current = words.next().expect("fewer than len set bits");
+            }
+            let (w, word) = current;
+            current.1 &= word - 1;
+
+            let high = (w * 64 + word.trailing_zeros() as usize - i) as u64;
+            (high << self.low_bits) | self.low(i)
+        })
+    }
+
+    fn keys(&self) -> impl Iterator<Item = u64> + '_ {
+        self.offsets().map(|offset| self.base + offset)
+    }
+
+    /// Iterates over the values in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = TotalF64> + '_ {
+        self.keys().map(from_ordered_u64)
+    }
+
+    /// Returns the values in either set
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_sorted(&union(self.keys(), other.keys()))
+    }
+
+    /// Returns the values in both sets
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::from_sorted(&intersection(self.keys(), other.keys()))
+    }
+
+    /// Encodes the set as bytes, see [`from_bytes`](Self::from_bytes).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(28 + 8 * (self.lower.len() + self.upper.len()));
+        out.extend_from_slice(&(self.len as u64).to_le_bytes());
+        out.extend_from_slice(&self.base.to_le_bytes());
+        out.extend_from_slice(&self.low_bits.to_le_bytes());
+        out.extend_from_slice(&(self.upper_len as u64).to_le_bytes());
+        for word in self.lower.iter().chain(&self.upper) {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    /// Decodes a set encoded by [`to_bytes`](Self::to_bytes), checking that it is valid.
+    ///
+    /// The encoding is the little-endian `u64` length, `u64` base key, `u32` number of low bits and
+    /// `u64` number of upper bits, followed by the packed lower and upper bits as little-endian
+    /// `u64` words.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader(bytes);
+        let len = reader.usize()?;
+        let base = reader.u64()?;
+        let low_bits = reader.u32()?;
+        let upper_len = reader.usize()?;
+        if low_bits > 63 || (len == 0 && (base != 0 || low_bits != 0 || upper_len != 0)) {
+            return Err(DecodeError::Invalid);
+        }
+
+        let lower_bits = len
+            .checked_mul(low_bits as usize)
+            .ok_or(DecodeError::Invalid)?;
+        let lower = reader.words(lower_bits.div_ceil(64))?;
+        let upper = reader.words(upper_len.div_ceil(64))?;
+        reader.finish()?;
+
+        // the padding must be clear, and every value must be followed by the end of its bucket
+        let padding_clear = |words: &[u64], bits: usize| {
+            bits.is_multiple_of(64) || words.last().is_some_and(|w| w >> (bits % 64) == 0)
+        };
+        let ones: usize = upper.iter().map(|w| w.count_ones() as usize).sum();
+        let ends_clear =
+            upper_len == 0 || upper[(upper_len - 1) / 64] & (1 << ((upper_len - 1) % 64)) == 0;
+        if !padding_clear(&lower, lower_bits)
+            || !padding_clear(&upper, upper_len)
+            || ones != len
+            || !ends_clear
+        {
+            return Err(DecodeError::Invalid);
+        }
+
+        let set = Self::with_index(len, base, low_bits, lower, upper, upper_len);
+        let increasing = set.offsets().zip(set.offsets().skip(1)).all(|(a, b)| a < b);
+        // the encoding of a set is unique, so that equal sets compare equal
+        let canonical = set.len == 0 || {
+            let max = set.offset(set.len - 1);
+            set.offset(0) == 0
+                && base.checked_add(max).is_some()
+                && set.low_bits == Self::low_bits_for(set.len, max)
+                && set.upper_len == set.len + (max >> set.low_bits) as usize + 1
+        };
+        if !increasing || !canonical {
+            return Err(DecodeError::Invalid);
+        }
+
+        Ok(set)
+    }
+}
+
+impl FromIterator<TotalF64> for CompressedF64Set {
+    fn from_iter<I: IntoIterator<Item = TotalF64>>(iter: I) -> Self {
+        let mut keys: Vec<u64> = iter.into_iter().map(ordered_u64).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        Self::from_sorted(&keys)
+    }
+}
+
+/// The most values a roaring container stores as a sorted array, above it a bitmap is smaller
+const ARRAY_MAX: usize = 4096;
+
+/// The values of a `CompressedF32Set` which share their high 16 bits
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Container {
+    Array(Vec<u16>),
+    Bitmap(Box<[u64; 1024]>),
+}
+
+impl Container {
+    fn from_sorted(lows: &[u16]) -> Self {
+        if lows.len() <= ARRAY_MAX {
+            return Container::Array(lows.to_vec());
+        }
+
+        let mut bitmap = Box::new([0; 1024]);
+        for &low in lows {
+            bitmap[low as usize / 64] |= 1 << (low % 64);
+        }
+        Container::Bitmap(bitmap)
+    }
+
+    /// Returns the number of values in the container which are less than `low`
+    fn rank(&self, low: u16) -> usize {
+        match self {
+            Container::Array(lows) => lows.partition_point(|&l| l < low),
+            Container::Bitmap(bitmap) => {
+                let (word, bit) = (low as usize / 64, low % 64);
+                let before: u32 = bitmap[..word].iter().map(|w| w.count_ones()).sum();
+                (before + (bitmap[word] & ((1 << bit) - 1)).count_ones()) as usize
+            }
+        }
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        match self {
+            Container::Array(lows) => lows.binary_search(&low).is_ok(),
+            Container::Bitmap(bitmap) => bitmap[low as usize / 64] & (1 << (low % 64)) != 0,
+        }
+    }
+
+    fn select(&self, mut k: usize) -> u16 {
+        match self {
+            Container::Array(lows) => lows[k],
+            Container::Bitmap(bitmap) => {
+                for (w, &word) in bitmap.iter().enumerate() {
+                    let ones = word.count_ones() as usize;
+                    if k < ones {
+                        return (w * 64 + select_in_word(word, k)) as u16;
+                    }
+                    k -= ones;
+                }
+                unreachable!("fewer than k values in the container")
+            }
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        let (array, bitmap) = match self {
+            Container::Array(lows) => (Some(lows.iter().copied()), None),
+            Container::Bitmap(bitmap) => (None, Some(bitmap)),
+        };
+
+        let bits = bitmap.into_iter().flat_map(|bitmap| {
+            bitmap.iter().enumerate().flat_map(|(w, &word)| {
+                let mut word = word;
+                core::iter::from_fn(move || {
+                    let bit = (word != 0).then(|| (w * 64) as u16 + word.trailing_zeros() as u16);
+                    word &= word.wrapping_sub(1);
+                    bit
+                })
+            })
+        });
+        array.into_iter().flatten().chain(bits)
+    }
+}
+
+/// An immutable set of `TotalF32`, compressed as a roaring bitmap.
+///
+/// Each value is mapped to a `u32` with the same order, and the values are grouped by their high
+/// 16 bits. Each group stores its low 16 bits as a sorted array when it is sparse, or as a
+/// 65536 bit bitmap when it is dense, so both scattered and clustered sets stay compact.
+///
+/// Iteration follows the total order.
+///
+/// ```rust
+/// use total_float_wrap::{CompressedF32Set, TotalF32};
+///
+/// let a: CompressedF32Set = [1.0, 2.0, 3.0].map(TotalF32).into_iter().collect();
+/// let b: CompressedF32Set = [2.0, 3.0, 4.0].map(TotalF32).into_iter().collect();
+///
+/// let both: Vec<f32> = a.intersection(&b).iter().map(|v| v.0).collect();
+/// assert_eq!(both, [2.0, 3.0]);
+/// assert_eq!(a.union(&b).len(), 4);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompressedF32Set {
+    highs: Vec<u16>,
+    containers: Vec<Container>,
+    /// The number of values before each container, and the total at the end
+    starts: Vec<usize>,
+}
+
+impl CompressedF32Set {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes strictly increasing ordered keys
+    fn from_sorted(keys: &[u32]) -> Self {
+        let mut highs = Vec::new();
+        let mut containers = Vec::new();
+        for group in keys.chunk_by(|a, b| a >> 16 == b >> 16) {
+            let lows: Vec<u16> = group.iter().map(|&k| k as u16).collect();
+            highs.push((group[0] >> 16) as u16);
+            containers.push(Container::from_sorted(&lows));
+        }
+        Self::with_starts(highs, containers)
+    }
+
+    fn with_starts(highs: Vec<u16>, containers: Vec<Container>) -> Self {
+        let mut starts = vec![0];
+        for container in &containers {
+            let len = match container {
+                Container::Array(lows) => lows.len(),
+                Container::Bitmap(bitmap) => bitmap.iter().map(|w| w.count_ones() as usize).sum(),
+            };
+            starts.push(starts.last().unwrap() + len);
+        }
+
+        Self {
+            highs,
+            containers,
+            starts,
+        }
+    }
+
+    /// Returns the number of values in the set
+    pub fn len(&self) -> usize {
+        *self.starts.last().unwrap_or(&0)
+    }
+
+    /// Returns whether the set contains no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the set contains the value
+    pub fn contains(&self, value: TotalF32) -> bool {
+        let key = ordered_u32(value);
+        match self.highs.binary_search(&((key >> 16) as u16)) {
+            Ok(c) => self.containers[c].contains(key as u16),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the number of values in the set which are less than `value`
+    pub fn rank(&self, value: TotalF32) -> usize {
+        let key = ordered_u32(value);
+        match self.highs.binary_search(&((key >> 16) as u16)) {
+            Ok(c) => self.starts[c] + self.containers[c].rank(key as u16),
+            Err(c) => self.starts.get(c).copied().unwrap_or(0),
+        }
+    }
+
+    /// Returns the `k`th smallest value in the set, counting from zero
+    pub fn select(&self, k: usize) -> Option<TotalF32> {
+        if k >= self.len() {
+            return None;
+        }
+
+        let c = self.starts.partition_point(|&start| start <= k) - 1;
+        let low = self.containers[c].select(k - self.starts[c]);
+        Some(from_ordered_u32(
+            ((self.highs[c] as u32) << 16) | low as u32,
+        ))
+    }
+
+    fn keys(&self) -> impl Iterator<Item = u32> + '_ {
+        self.highs
+            .iter()
+            .zip(&self.containers)
+            .flat_map(|(&high, c)| c.iter().map(move |low| ((high as u32) << 16) | low as u32))
+    }
+
+    /// Iterates over the values in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = TotalF32> + '_ {
+        self.keys().map(from_ordered_u32)
+    }
+
+    /// Returns the values in either set
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_sorted(&union(self.keys(), other.keys()))
+    }
+
+    /// Returns the values in both sets
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::from_sorted(&intersection(self.keys(), other.keys()))
+    }
+
+    /// Encodes the set as bytes, see [`from_bytes`](Self::from_bytes).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.containers.len() as u32).to_le_bytes());
+        for (&high, container) in self.highs.iter().zip(&self.containers) {
+            out.extend_from_slice(&high.to_le_bytes());
+            match container {
+                Container::Array(lows) => {
+                    out.push(0);
+                    out.extend_from_slice(&(lows.len() as u16).to_le_bytes());
+                    for low in lows {
+                        out.extend_from_slice(&low.to_le_bytes());
+                    }
+                }
+                Container::Bitmap(bitmap) => {
+                    out.push(1);
+                    for word in bitmap.iter() {
+                        out.extend_from_slice(&word.to_le_bytes());
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Decodes a set encoded by [`to_bytes`](Self::to_bytes), checking that it is valid.
+    ///
+    /// The encoding is the little-endian `u32` number of containers, followed by each container's
+    /// `u16` high bits and a kind byte. Arrays, kind 0, follow with a `u16` length and their
+    /// sorted `u16` values, and bitmaps, kind 1, with 1024 `u64` words.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader(bytes);
+        let count = reader.u32()? as usize;
+
+        let mut highs: Vec<u16> = Vec::new();
+        let mut containers = Vec::new();
+        for _ in 0..count {
+            let high = reader.u16()?;
+            if highs.last().is_some_and(|&last| last >= high) {
+                return Err(DecodeError::Invalid);
+            }
+
+            let container = match reader.u8()? {
+                0 => {
+                    let len = reader.u16()? as usize;
+                    let lows: Vec<u16> =
+                        (0..len).map(|_| reader.u16()).collect::<Result<_, _>>()?;
+                    if len == 0 || len > ARRAY_MAX || lows.windows(2).any(|w| w[0] >= w[1]) {
+                        return Err(DecodeError::Invalid);
+                    }
+                    Container::Array(lows)
+                }
+                1 => {
+                    let words = reader.words(1024)?;
+                    let ones: usize = words.iter().map(|w| w.count_ones() as usize).sum();
+                    if ones <= ARRAY_MAX {
+                        return Err(DecodeError::Invalid);
+                    }
+                    Container::Bitmap(Box::new(words.try_into().unwrap()))
+                }
+                _ => return Err(DecodeError::Invalid),
+            };
+            highs.push(high);
+            containers.push(container);
+        }
+        reader.finish()?;
+
+        Ok(Self::with_starts(highs, containers))
+    }
+}
+
+impl FromIterator<TotalF32> for CompressedF32Set {
+    fn from_iter<I: IntoIterator<Item = TotalF32>>(iter: I) -> Self {
+        let mut keys: Vec<u32> = iter.into_iter().map(ordered_u32).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        Self::from_sorted(&keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SortedTotalVec;
+
+    fn f64_values() -> Vec<TotalF64> {
+        let mut values: Vec<f64> = vec![
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            -1e300,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            f64::INFINITY,
+            f64::NAN,
+        ];
+        values.extend((0..500).map(|i| 1000.0 + i as f64 * 0.25));
+        values.into_iter().map(TotalF64).collect()
+    }
+
+    #[test]
+    fn test_compressed_f64_set_matches_sorted() {
+        let values = f64_values();
+        for n in [0, 1, 2, 8, 100, values.len()] {
+            let set: CompressedF64Set = values[..n].iter().copied().collect();
+            let sorted: SortedTotalVec = values[..n].iter().copied().collect();
+            assert_eq!(set.len(), sorted.len());
+            assert!(set.iter().eq(sorted.iter()), "{n}");
+
+            for k in 0..=set.len() {
+                assert_eq!(set.select(k), sorted.select(k));
+            }
+            for &q in values.iter().chain(&[TotalF64(0.5), TotalF64(1000.1)]) {
+                assert_eq!(set.rank(q), sorted.rank(q), "{n} {q:?}");
+                assert_eq!(set.contains(q), sorted.contains(q), "{n} {q:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_compressed_f64_set_ops_and_bytes() {
+        let a: CompressedF64Set = (0..100).map(|i| TotalF64(i as f64)).collect();
+        let b: CompressedF64Set = (50..150).map(|i| TotalF64(i as f64)).collect();
+
+        assert_eq!(a.union(&b), (0..150).map(|i| TotalF64(i as f64)).collect());
+        assert_eq!(
+            a.intersection(&b),
+            (50..100).map(|i| TotalF64(i as f64)).collect()
+        );
+        assert!(a.intersection(&CompressedF64Set::new()).is_empty());
+
+        for set in [
+            a,
+            CompressedF64Set::new(),
+            [f64::MAX].map(TotalF64).into_iter().collect(),
+        ] {
+            let bytes = set.to_bytes();
+            assert_eq!(CompressedF64Set::from_bytes(&bytes), Ok(set));
+            assert_eq!(
+                CompressedF64Set::from_bytes(&bytes[..bytes.len() - 1]),
+                Err(DecodeError::Truncated)
+            );
+        }
+
+        let mut bytes = b.to_bytes();
+        *bytes.last_mut().unwrap() |= 0x80;
+        assert_eq!(
+            CompressedF64Set::from_bytes(&bytes),
+            Err(DecodeError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_compressed_f64_set_is_compact() {
+        // values a few ulps apart, as samples from a narrow range are, need a few bits each
+        let set: CompressedF64Set = (0..10_000)
+            .map(|i| TotalF64(1.0 + (3 * i) as f64 * f64::EPSILON))
+            .collect();
+        assert!(set.to_bytes().len() < 10_000, "{}", set.to_bytes().len());
+    }
+
+    #[test]
+    fn test_compressed_f32_set_matches_sorted() {
+        // a dense run of consecutive floats fills a bitmap container
+        let dense = (0..5000).map(|i| f32::from_bits(1.0_f32.to_bits() + i));
+        let sparse = [-f32::NAN, f32::NEG_INFINITY, -2.0, -0.0, 0.0, 3.5, f32::NAN];
+        let values: Vec<TotalF32> = dense.chain(sparse).map(TotalF32).collect();
+
+        let set: CompressedF32Set = values.iter().copied().collect();
+        assert!(set
+            .containers
+            .iter()
+            .any(|c| matches!(c, Container::Bitmap(_))));
+
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert_eq!(set.len(), sorted.len());
+        assert!(set.iter().eq(sorted.iter().copied()));
+
+        for (k, &v) in sorted.iter().enumerate() {
+            assert!(set.contains(v));
+            assert_eq!(set.rank(v), k);
+            assert_eq!(set.select(k), Some(v));
+        }
+        assert_eq!(
+            set.rank(TotalF32(1.5)),
+            sorted.partition_point(|v| v.0 < 1.5)
+        );
+        assert!(!set.contains(TotalF32(100.0)));
+        assert_eq!(set.select(sorted.len()), None);
+
+        let bytes = set.to_bytes();
+        assert_eq!(CompressedF32Set::from_bytes(&bytes), Ok(set.clone()));
+        assert_eq!(
+            CompressedF32Set::from_bytes(&bytes[..10]),
+            Err(DecodeError::Truncated)
+        );
+
+        let evens: CompressedF32Set = sorted.iter().copied().step_by(2).collect();
+        assert_eq!(set.intersection(&evens), evens);
+        assert_eq!(set.union(&evens), set);
+    }
+}
@@ -0,0 +1,293 @@
+use core::cmp::Reverse;
+use core::fmt;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::{FloatBuildHasher, TotalF64};
+
+/// Where an [`EventQueue`] puts events scheduled at a NaN time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum NanPolicy {
+    /// Refuse to schedule the event, returning a [`NanTimeError`].
+    #[default]
+    Reject,
+    /// Schedule the event before every other event, at `-NaN`.
+    First,
+    /// Schedule the event after every other event, at `NaN`.
+    Last,
+}
+
+/// Error returned when an event is scheduled at a NaN time under [`NanPolicy::Reject`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NanTimeError;
+
+impl fmt::Display for NanTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("event time is NaN")
+    }
+}
+
+impl std::error::Error for NanTimeError {}
+
+/// Identifies an event scheduled on an [`EventQueue`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EventHandle(u64);
+
+#[derive(Debug, Clone)]
+struct Pending<T> {
+    time: TotalF64,
+    order: u64,
+    event: T,
+}
+
+/// A min-priority queue of events keyed by time, for discrete-event simulation.
+///
+/// Events at equal times come out in the order they were scheduled, and a rescheduled event
+/// counts as scheduled when it was moved. Times are compared by the total order, except that
+/// `-0.0` is treated as `0.0`, and NaN times are handled by the [`NanPolicy`].
+///
+/// ```rust
+/// use total_float_wrap::{EventQueue, TotalF64};
+///
+/// let mut queue = EventQueue::new();
+/// queue.schedule(2.0, "second").unwrap();
+/// let cancelled = queue.schedule(1.0, "cancelled").unwrap();
+/// queue.schedule(0.5, "first").unwrap();
+/// queue.schedule(2.0, "third").unwrap();
+/// assert!(queue.schedule(f64::NAN, "never").is_err());
+///
+/// assert_eq!(queue.cancel(cancelled), Some("cancelled"));
+/// assert_eq!(queue.peek_time(), Some(TotalF64(0.5)));
+///
+/// let order: Vec<&str> = std::iter::from_fn(|| queue.pop_next()).map(|(_, e)| e).collect();
+/// assert_eq!(order, ["first", "second", "third"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventQueue<T> {
+    heap: BinaryHeap<Reverse<(TotalF64, u64, u64)>>,
+    pending: HashMap<u64, Pending<T>, FloatBuildHasher>,
+    nan_policy: NanPolicy,
+    next_handle: u64,
+    next_order: u64,
+}
+
+impl<T> EventQueue<T> {
+    /// Creates an empty queue which rejects NaN times.
+    pub fn new() -> Self {
+        Self::with_nan_policy(NanPolicy::default())
+    }
+
+    /// Creates a queue which handles NaN times according to `nan_policy`.
+    pub fn with_nan_policy(nan_policy: NanPolicy) -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            pending: HashMap::default(),
+            nan_policy,
+            next_handle: 0,
+            next_order: 0,
+        }
+    }
+
+    /// Returns the number of pending events
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether there are no pending events
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Returns whether the event is still pending
+    pub fn contains(&self, handle: EventHandle) -> bool {
+        self.pending.contains_key(&handle.0)
+    }
+
+    /// Returns the time the event is scheduled for, if it is still pending
+    pub fn time_of(&self, handle: EventHandle) -> Option<TotalF64> {
+        self.pending.get(&handle.0).map(|p| p.time)
+    }
+
+    /// Schedules the event at `time`, returning a handle to cancel or reschedule it with.
+    pub fn schedule(&mut self, time: f64, event: T) -> Result<EventHandle, NanTimeError> {
+        let time = self.canonical_time(time)?;
+        let handle = self.next_handle;
+        self.next_handle += 1;
+
+        let order = self.push(time, handle);
+        self.pending.insert(handle, Pending { time, order, event });
+        Ok(EventHandle(handle))
+    }
+
+    /// Returns the time of the next event
+    pub fn peek_time(&self) -> Option<TotalF64> {
+        self.heap.peek().map(|Reverse((time, _, _))| *time)
+    }
+
+    /// Removes the next event, returning it with its time
+    pub fn pop_next(&mut self) -> Option<(TotalF64, T)> {
+        let Reverse((_, _, handle)) = self.heap.pop()?;
+        let pending = self.pending.remove(&handle).expect("top of heap is live");
+        self.prune();
+        Some((pending.time, pending.event))
+    }
+
+    /// Cancels the event, returning it if it was still pending
+    pub fn cancel(&mut self, handle: EventHandle) -> Option<T> {
+        let pending = self.pending.remove(&handle.0)?;
+        self.prune();
+        Some(pending.event)
+    }
+
+    /// Moves the event to `time`, after any events already scheduled at that time.
+    ///
+    /// Returns whether the event was still pending, an event which has been popped or cancelled
+    /// is not scheduled again.
+    pub fn reschedule(&mut self, handle: EventHandle, time: f64) -> Result<bool, NanTimeError> {
+        let time = self.canonical_time(time)?;
+        if !self.pending.contains_key(&handle.0) {
+            return Ok(false);
+        }
+
+        let order = self.push(time, handle.0);
+        let pending = self.pending.get_mut(&handle.0).expect("event is pending");
+        pending.time = time;
+        pending.order = order;
+        self.prune();
+        Ok(true)
+    }
+
+    /// Applies the NaN policy and merges the zeros
+    fn canonical_time(&self, time: f64) -> Result<TotalF64, NanTimeError> {
+        if time.is_nan() {
+            match self.nan_policy {
+                NanPolicy::Reject => Err(NanTimeError),
+                NanPolicy::First => Ok(TotalF64(-f64::NAN)),
+                NanPolicy::Last => Ok(TotalF64(f64::NAN)),
+            }
+        } else if time == 0.0 {
+            Ok(TotalF64(0.0))
+        } else {
+            Ok(TotalF64(time))
+        }
+    }
+
+    /// Pushes an entry for the event, returning its position in the insertion order
+    fn push(&mut self, time: TotalF64, handle: u64) -> u64 {
+        let order = self.next_order;
+        self.next_order += 1;
+        self.heap.push(Reverse((time, order, handle)));
+        order
+    }
+
+    /// Drops entries left behind by cancelled and rescheduled events from the top of the heap,
+    /// and rebuilds the heap once most of it is stale.
+    fn prune(&mut self) {
+        let live = |pending: &HashMap<u64, Pending<T>, FloatBuildHasher>, order, handle| {
+            pending.get(&handle).is_some_and(|p| p.order == order)
+        };
+
+        if self.heap.len() > 2 * self.pending.len() + 16 {
+            let pending = &self.pending;
+            self.heap
+                .retain(|Reverse((_, order, handle))| live(pending, *order, *handle));
+        }
+        while let Some(&Reverse((_, order, handle))) = self.heap.peek() {
+            if live(&self.pending, order, handle) {
+                break;
+            }
+            self.heap.pop();
+        }
+    }
+}
+
+impl<T> Default for EventQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain<T>(queue: &mut EventQueue<T>) -> Vec<(f64, T)> {
+        core::iter::from_fn(|| queue.pop_next())
+            .map(|(t, e)| (t.0, e))
+            .collect()
+    }
+
+    #[test]
+    fn test_fifo_ties_and_zeros() {
+        let mut queue = EventQueue::new();
+        for (i, time) in [1.0, 0.0, -0.0, 1.0, 0.0, -1.0].into_iter().enumerate() {
+            queue.schedule(time, i).unwrap();
+        }
+
+        let events = drain(&mut queue);
+        assert_eq!(
+            events.iter().map(|e| e.1).collect::<Vec<_>>(),
+            [5, 1, 2, 4, 0, 3]
+        );
+        assert!(events.iter().all(|e| e.0.to_bits() != (-0.0_f64).to_bits()));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_and_reschedule() {
+        let mut queue = EventQueue::new();
+        let a = queue.schedule(1.0, 'a').unwrap();
+        let b = queue.schedule(2.0, 'b').unwrap();
+        let c = queue.schedule(2.0, 'c').unwrap();
+        let d = queue.schedule(3.0, 'd').unwrap();
+
+        // moved events go behind those already waiting at the new time
+        assert_eq!(queue.reschedule(a, 2.0), Ok(true));
+        assert_eq!(queue.time_of(a), Some(TotalF64(2.0)));
+        assert_eq!(queue.peek_time(), Some(TotalF64(2.0)));
+        assert_eq!(queue.reschedule(d, 0.5), Ok(true));
+        assert_eq!(queue.cancel(b), Some('b'));
+        assert_eq!(queue.cancel(b), None);
+        assert_eq!(queue.reschedule(b, 0.0), Ok(false));
+        assert!(!queue.contains(b));
+        assert_eq!(queue.len(), 3);
+
+        assert_eq!(drain(&mut queue), [(0.5, 'd'), (2.0, 'c'), (2.0, 'a')]);
+        assert_eq!(queue.cancel(c), None);
+    }
+
+    #[test]
+    fn test_nan_policy() {
+        let mut queue = EventQueue::new();
+        let handle = queue.schedule(1.0, 0).unwrap();
+        assert_eq!(queue.schedule(f64::NAN, 1), Err(NanTimeError));
+        assert_eq!(queue.reschedule(handle, f64::NAN), Err(NanTimeError));
+
+        for (policy, expected) in [(NanPolicy::First, [1, 0, 2]), (NanPolicy::Last, [0, 2, 1])] {
+            let mut queue = EventQueue::with_nan_policy(policy);
+            queue.schedule(f64::NEG_INFINITY, 0).unwrap();
+            queue.schedule(-f64::NAN, 1).unwrap();
+            queue.schedule(f64::INFINITY, 2).unwrap();
+            let order: Vec<i32> = drain(&mut queue).into_iter().map(|e| e.1).collect();
+            assert_eq!(order, expected);
+        }
+    }
+
+    #[test]
+    fn test_stale_entries_are_dropped() {
+        let mut queue = EventQueue::new();
+        let handles: Vec<EventHandle> = (0..100)
+            .map(|i| queue.schedule(f64::from(i), i).unwrap())
+            .collect();
+        for &handle in &handles[1..] {
+            for time in [500.0, 400.0, 300.0] {
+                queue.reschedule(handle, time).unwrap();
+            }
+        }
+        assert!(queue.heap.len() <= 2 * queue.len() + 16);
+
+        for &handle in &handles[..99] {
+            queue.cancel(handle);
+        }
+        assert_eq!(drain(&mut queue), [(300.0, 99)]);
+    }
+}
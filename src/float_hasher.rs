@@ -0,0 +1,182 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
+
+use crate::TotalF64;
+
+/// A `HashMap` keyed by `TotalF64` using the [`FloatBuildHasher`].
+pub type FloatMap<V> = HashMap<TotalF64, V, FloatBuildHasher>;
+
+/// A `HashSet` of `TotalF64` using the [`FloatBuildHasher`].
+pub type FloatSet = HashSet<TotalF64, FloatBuildHasher>;
+
+/// A `BuildHasher` tuned for the float wrappers, producing [`FloatHasher`]s.
+///
+/// The wrappers hash a single normalised integer, which is already a well distributed key for
+/// most data, so SipHash does far more work than needed.
+/// Each builder draws a random seed and an odd multiplier when it is created, so the mapping
+/// from keys to buckets cannot be predicted ahead of time.
+#[derive(Debug, Copy, Clone)]
+pub struct FloatBuildHasher {
+    seed: u64,
+    multiplier: u64,
+}
+
+impl FloatBuildHasher {
+    /// Creates a builder with a random seed, drawn from std's `RandomState`.
+    pub fn new() -> Self {
+        let state = RandomState::new();
+        Self::with_seed(state.hash_one(0_u64), state.hash_one(1_u64))
+    }
+
+    /// Creates a builder from a fixed seed and multiplier.
+    ///
+    /// Hashes are then reproducible, but give up resistance to crafted keys.
+    pub fn with_seed(seed: u64, multiplier: u64) -> Self {
+        Self {
+            seed,
+            // the multiplier must be odd to be a bijection on the low bits
+            multiplier: multiplier | 1,
+        }
+    }
+}
+
+impl Default for FloatBuildHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuildHasher for FloatBuildHasher {
+    type Hasher = FloatHasher;
+
+    fn build_hasher(&self) -> FloatHasher {
+        FloatHasher {
+            state: self.seed,
+            multiplier: self.multiplier,
+        }
+    }
+}
+
+/// A multiply-shift hasher for the normalised integers the float wrappers hash.
+///
+/// Each 64 bit word written is mixed into the state with a full 128 bit multiply, folding the
+/// high half of the product onto the low half so that every output bit depends on every input bit.
+/// Byte slices are processed in little-endian 8 byte words, so other key types still work.
+#[derive(Debug, Clone)]
+pub struct FloatHasher {
+    state: u64,
+    multiplier: u64,
+}
+
+impl FloatHasher {
+    fn mix(&mut self, word: u64) {
+        let product = ((self.state ^ word) as u128) * self.multiplier as u128;
+        self.state = (product as u64) ^ ((product >> 64) as u64);
+    }
+}
+
+impl Hasher for FloatHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.mix(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut word = [0; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            self.mix(u64::from_le_bytes(word));
+        }
+
+        // without the length, slices ending in zero bytes would collide with shorter ones
+        self.mix(bytes.len() as u64);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.mix(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.mix(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.mix(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.mix(i);
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.mix(i as u64);
+        self.mix((i >> 64) as u64);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.mix(i as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_float_hasher_deterministic_per_builder() {
+        let builder = FloatBuildHasher::new();
+        assert_eq!(
+            builder.hash_one(TotalF64(1.5)),
+            builder.hash_one(TotalF64(1.5))
+        );
+        assert_ne!(
+            builder.hash_one(TotalF64(1.5)),
+            builder.hash_one(TotalF64(-1.5))
+        );
+
+        let fixed = FloatBuildHasher::with_seed(1, 2);
+        assert_eq!(
+            fixed.hash_one(TotalF64(1.5)),
+            FloatBuildHasher::with_seed(1, 2).hash_one(TotalF64(1.5))
+        );
+    }
+
+    #[test]
+    fn test_float_hasher_seeded_per_instance() {
+        let (a, b) = (FloatBuildHasher::new(), FloatBuildHasher::new());
+        let differs =
+            (0..16).any(|i| a.hash_one(TotalF64(i as f64)) != b.hash_one(TotalF64(i as f64)));
+        assert!(differs);
+    }
+
+    #[test]
+    fn test_float_hasher_low_bits_spread() {
+        // small integral floats only differ in their high mantissa bits, while hash maps index
+        // buckets by the low bits of the hash, so these must still spread out
+        let builder = FloatBuildHasher::with_seed(0, 0x9e37_79b9_7f4a_7c15);
+        let buckets: HashSet<u64> = (0..256)
+            .map(|i| builder.hash_one(TotalF64(1.0 + i as f64)) & 0xff)
+            .collect();
+        assert!(buckets.len() > 128, "{}", buckets.len());
+    }
+
+    #[test]
+    fn test_float_map() {
+        let mut map: FloatMap<u32> = FloatMap::default();
+        map.insert(TotalF64(0.5), 1);
+        map.insert(TotalF64(-0.0), 2);
+        map.insert(TotalF64(0.0), 3);
+        assert_eq!(map.get(&TotalF64(0.5)), Some(&1));
+        assert_eq!(map.get(&TotalF64(-0.0)), Some(&2));
+        assert_eq!(map.len(), 3);
+
+        let set: FloatSet = [1.0, 2.0, 1.0].into_iter().map(TotalF64).collect();
+        assert_eq!(set.len(), 2);
+    }
+}
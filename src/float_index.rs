@@ -0,0 +1,703 @@
+use core::fmt;
+use core::ops::{Bound, RangeBounds};
+
+use crate::{StableHasher, TotalF32, TotalF64};
+
+/// The first bytes of every index file
+pub const INDEX_MAGIC: [u8; 4] = *b"TFIX";
+
+/// The version of the index file format written by [`IndexWriter`].
+pub const INDEX_VERSION: u16 = 1;
+
+/// The length of the header at the start of every index file
+const HEADER_LEN: usize = 40;
+
+/// The offset of the checksum within the header, it covers every byte except its own
+const CHECKSUM_OFFSET: usize = 32;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// The float type of the keys in an index file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum KeyKind {
+    F64,
+    F32,
+}
+
+impl KeyKind {
+    fn width(self) -> usize {
+        match self {
+            KeyKind::F64 => 8,
+            KeyKind::F32 => 4,
+        }
+    }
+}
+
+/// How the values of an index file are stored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ValueLayout {
+    /// Every value is exactly this many bytes long, and they are stored back to back.
+    Fixed(u32),
+    /// Values may have any length, and are found through a table of offsets.
+    Variable,
+}
+
+/// Key types which can be written to an index file.
+///
+/// This trait is sealed, it is implemented for `TotalF64` and `TotalF32`.
+pub trait IndexKey: Copy + Ord + private::Sealed {
+    /// The kind recorded in the header.
+    #[doc(hidden)]
+    const KIND: KeyKind;
+
+    /// Appends the normalised key in little-endian order.
+    #[doc(hidden)]
+    fn write_key(self, out: &mut Vec<u8>);
+}
+
+impl private::Sealed for TotalF64 {}
+
+impl IndexKey for TotalF64 {
+    const KIND: KeyKind = KeyKind::F64;
+
+    fn write_key(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.normalise().to_le_bytes());
+    }
+}
+
+impl private::Sealed for TotalF32 {}
+
+impl IndexKey for TotalF32 {
+    const KIND: KeyKind = KeyKind::F32;
+
+    fn write_key(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.normalise().to_le_bytes());
+    }
+}
+
+/// Error returned when an index cannot be written or read.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IndexError {
+    /// The bytes do not start with [`INDEX_MAGIC`].
+    BadMagic,
+    /// The file was written with a format version this crate cannot read.
+    UnsupportedVersion(u16),
+    /// The header holds a value which no writer produces.
+    InvalidHeader,
+    /// The file is shorter than its header says, or its value offsets point outside of it.
+    Truncated,
+    /// The checksum does not match the contents of the file.
+    ChecksumMismatch,
+    /// The keys are not strictly increasing.
+    Unsorted,
+    /// A value does not have the size required by [`ValueLayout::Fixed`].
+    ValueSize { expected: u32, found: usize },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::BadMagic => f.write_str("not a float index file"),
+            IndexError::UnsupportedVersion(v) => write!(f, "unsupported index version {v}"),
+            IndexError::InvalidHeader => f.write_str("index header is invalid"),
+            IndexError::Truncated => f.write_str("index file is truncated"),
+            IndexError::ChecksumMismatch => f.write_str("index checksum does not match"),
+            IndexError::Unsorted => f.write_str("index keys are not sorted"),
+            IndexError::ValueSize { expected, found } => {
+                write!(f, "value is {found} bytes, expected {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IndexError {}
+
+/// Builds an index file from keys of type `K` and their values.
+///
+/// The file is laid out as follows, with every integer little-endian:
+///
+/// | bytes  | contents                                                  |
+/// |--------|-----------------------------------------------------------|
+/// | 0..4   | [`INDEX_MAGIC`]                                           |
+/// | 4..6   | [`INDEX_VERSION`] as a `u16`                              |
+/// | 6      | key kind, 0 for `f64` and 1 for `f32`                     |
+/// | 7      | value layout, 0 for fixed size and 1 for variable size    |
+/// | 8..12  | the fixed value size as a `u32`, 0 for variable values    |
+/// | 12..20 | the number of keys as a `u64`                             |
+/// | 20..24 | the sparse index block size as a `u32`, 0 for none        |
+/// | 24..32 | reserved, zero                                            |
+/// | 32..40 | the [`StableHasher`] hash of every other byte of the file |
+///
+/// followed by the normalised keys in increasing order, as `i64` or `i32`, then one key from
+/// the start of every block when the sparse index is enabled, then the values.
+/// Fixed size values are stored back to back, while variable size values are preceded by
+/// `count + 1` `u64` offsets into the bytes which follow them.
+///
+/// ```rust
+/// use total_float_wrap::{IndexReader, IndexWriter, TotalF64, ValueLayout};
+///
+/// let mut writer = IndexWriter::new(ValueLayout::Variable);
+/// writer.insert(TotalF64(1.5), b"one and a half")?;
+/// writer.insert(TotalF64(-2.0), b"minus two")?;
+/// let bytes = writer.finish();
+///
+/// let reader = IndexReader::open(&bytes)?;
+/// assert_eq!(reader.get(TotalF64(1.5)), Some(&b"one and a half"[..]));
+/// assert_eq!(reader.range(TotalF64(0.0)..).count(), 1);
+/// # Ok::<(), total_float_wrap::IndexError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexWriter<K: IndexKey> {
+    layout: ValueLayout,
+    block_size: u32,
+    entries: Vec<(K, Vec<u8>)>,
+}
+
+impl<K: IndexKey> IndexWriter<K> {
+    /// Creates an empty writer for values stored with `layout`
+    pub fn new(layout: ValueLayout) -> Self {
+        Self {
+            layout,
+            block_size: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds a sparse index holding the first key of every block of `block_size` keys, which
+    /// keeps the first steps of a search within a small, contiguous part of the file.
+    ///
+    /// A block size of 0 disables the sparse index, which is the default.
+    pub fn block_size(self, block_size: u32) -> Self {
+        Self { block_size, ..self }
+    }
+
+    /// Adds an entry, replacing the value of any earlier entry with the same key.
+    pub fn insert(&mut self, key: K, value: &[u8]) -> Result<(), IndexError> {
+        if let ValueLayout::Fixed(size) = self.layout {
+            if value.len() != size as usize {
+                return Err(IndexError::ValueSize {
+                    expected: size,
+                    found: value.len(),
+                });
+            }
+        }
+
+        self.entries.push((key, value.to_vec()));
+        Ok(())
+    }
+
+    /// Writes the index file.
+    pub fn finish(mut self) -> Vec<u8> {
+        // the sort is stable, so reversing first leaves the last value of each key first
+        self.entries.reverse();
+        self.entries.sort_by_key(|&(k, _)| k);
+        self.entries.dedup_by_key(|&mut (k, _)| k);
+
+        let count = self.entries.len();
+        let (layout, value_size) = match self.layout {
+            ValueLayout::Fixed(size) => (0_u8, size),
+            ValueLayout::Variable => (1, 0),
+        };
+        let kind = match K::KIND {
+            KeyKind::F64 => 0_u8,
+            KeyKind::F32 => 1,
+        };
+
+        let mut out = Vec::with_capacity(HEADER_LEN + count * (K::KIND.width() + 8));
+        out.extend_from_slice(&INDEX_MAGIC);
+        out.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        out.extend_from_slice(&[kind, layout]);
+        out.extend_from_slice(&value_size.to_le_bytes());
+        out.extend_from_slice(&(count as u64).to_le_bytes());
+        out.extend_from_slice(&self.block_size.to_le_bytes());
+        out.extend_from_slice(&[0; 16]);
+
+        for &(k, _) in &self.entries {
+            k.write_key(&mut out);
+        }
+        if self.block_size != 0 {
+            for &(k, _) in self.entries.iter().step_by(self.block_size as usize) {
+                k.write_key(&mut out);
+            }
+        }
+
+        if self.layout == ValueLayout::Variable {
+            let mut offset = 0_u64;
+            out.extend_from_slice(&offset.to_le_bytes());
+            for (_, v) in &self.entries {
+                offset += v.len() as u64;
+                out.extend_from_slice(&offset.to_le_bytes());
+            }
+        }
+        for (_, v) in &self.entries {
+            out.extend_from_slice(v);
+        }
+
+        let checksum = checksum(&out);
+        out[CHECKSUM_OFFSET..HEADER_LEN].copy_from_slice(&checksum.to_le_bytes());
+        out
+    }
+}
+
+/// Hashes the file, skipping over the checksum itself
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(&bytes[..CHECKSUM_OFFSET]);
+    hasher.write(&bytes[HEADER_LEN..]);
+    hasher.finish()
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// Reads an index file written by [`IndexWriter`] in place.
+///
+/// Opening an index only checks its header and length, so it takes the same time whatever the
+/// size of the file, and every query reads just the keys and values it needs. This makes it
+/// suitable for memory mapped files.
+/// A corrupt file never causes a panic, but may give wrong answers unless [`verify`] has
+/// accepted it.
+///
+/// Queries take `TotalF64` keys and follow the total order. `f32` keys are widened, which is
+/// exact and preserves their order.
+///
+/// [`verify`]: IndexReader::verify
+#[derive(Debug, Copy, Clone)]
+pub struct IndexReader<'a> {
+    kind: KeyKind,
+    layout: ValueLayout,
+    count: usize,
+    block_size: usize,
+    keys: &'a [u8],
+    sparse: &'a [u8],
+    offsets: &'a [u8],
+    values: &'a [u8],
+    file: &'a [u8],
+}
+
+impl<'a> IndexReader<'a> {
+    /// Checks the header and splits the file into its sections.
+    pub fn open(file: &'a [u8]) -> Result<Self, IndexError> {
+        if file.len() < HEADER_LEN {
+            return match file.get(..4) {
+                Some(magic) if magic != INDEX_MAGIC => Err(IndexError::BadMagic),
+                _ => Err(IndexError::Truncated),
+            };
+        }
+        if file[..4] != INDEX_MAGIC {
+            return Err(IndexError::BadMagic);
+        }
+
+        let version = read_u16(file, 4);
+        if version != INDEX_VERSION {
+            return Err(IndexError::UnsupportedVersion(version));
+        }
+
+        let kind = match file[6] {
+            0 => KeyKind::F64,
+            1 => KeyKind::F32,
+            _ => return Err(IndexError::InvalidHeader),
+        };
+        let value_size = read_u32(file, 8);
+        let layout = match (file[7], value_size) {
+            (0, size) => ValueLayout::Fixed(size),
+            (1, 0) => ValueLayout::Variable,
+            _ => return Err(IndexError::InvalidHeader),
+        };
+        let count = usize::try_from(read_u64(file, 12)).map_err(|_| IndexError::Truncated)?;
+        let block_size = read_u32(file, 20) as usize;
+        if file[24..CHECKSUM_OFFSET].iter().any(|&b| b != 0) {
+            return Err(IndexError::InvalidHeader);
+        }
+
+        let blocks = if block_size == 0 {
+            0
+        } else {
+            count.div_ceil(block_size)
+        };
+        let offsets = match layout {
+            ValueLayout::Fixed(_) => 0,
+            ValueLayout::Variable => count.checked_add(1).ok_or(IndexError::Truncated)?,
+        };
+
+        let mut rest = &file[HEADER_LEN..];
+        let keys = split(&mut rest, count.checked_mul(kind.width()))?;
+        let sparse = split(&mut rest, Some(blocks * kind.width()))?;
+        let offsets = split(&mut rest, offsets.checked_mul(8))?;
+        let values = match layout {
+            ValueLayout::Fixed(size) => split(&mut rest, count.checked_mul(size as usize))?,
+            ValueLayout::Variable => core::mem::take(&mut rest),
+        };
+        if !rest.is_empty() {
+            return Err(IndexError::InvalidHeader);
+        }
+
+        Ok(Self {
+            kind,
+            layout,
+            count,
+            block_size,
+            keys,
+            sparse,
+            offsets,
+            values,
+            file,
+        })
+    }
+
+    /// Checks the whole file: the checksum, the order of the keys and the value offsets.
+    ///
+    /// This reads every byte of the file, so is best done once, after it has been written to or
+    /// read from storage.
+    pub fn verify(&self) -> Result<(), IndexError> {
+        if checksum(self.file) != read_u64(self.file, CHECKSUM_OFFSET) {
+            return Err(IndexError::ChecksumMismatch);
+        }
+
+        if (1..self.count).any(|i| self.raw_key(i - 1) >= self.raw_key(i)) {
+            return Err(IndexError::Unsorted);
+        }
+        let sparse_matches = (0..self.sparse.len() / self.kind.width())
+            .all(|b| self.raw_sparse_key(b) == self.raw_key(b * self.block_size));
+        if !sparse_matches {
+            return Err(IndexError::InvalidHeader);
+        }
+
+        if self.layout == ValueLayout::Variable {
+            let offsets_valid = (0..self.count).all(|i| self.offset(i) <= self.offset(i + 1))
+                && self.offset(0) == 0
+                && self.offset(self.count) == self.values.len() as u64;
+            if !offsets_valid {
+                return Err(IndexError::Truncated);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the float type of the keys in the file
+    pub fn key_kind(&self) -> KeyKind {
+        self.kind
+    }
+
+    /// Returns how the values are stored in the file
+    pub fn value_layout(&self) -> ValueLayout {
+        self.layout
+    }
+
+    /// Returns the number of entries in the index
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Returns whether the index contains no entries
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the `i`th smallest key, widened to a `TotalF64`.
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn key(&self, i: usize) -> TotalF64 {
+        TotalF64::from_normalised(self.raw_key(i))
+    }
+
+    /// Returns the value of the `i`th smallest key.
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn value(&self, i: usize) -> &'a [u8] {
+        let range = match self.layout {
+            ValueLayout::Fixed(size) => {
+                let size = size as usize;
+                Some(i * size..(i + 1) * size)
+            }
+            ValueLayout::Variable => usize::try_from(self.offset(i))
+                .ok()
+                .zip(usize::try_from(self.offset(i + 1)).ok())
+                .map(|(start, end)| start..end),
+        };
+        range.and_then(|r| self.values.get(r)).unwrap_or(&[])
+    }
+
+    /// Returns the value stored under the key
+    pub fn get(&self, key: TotalF64) -> Option<&'a [u8]> {
+        let key = key.normalise();
+        let i = self.partition_point(|k| k < key);
+        (i < self.count && self.raw_key(i) == key).then(|| self.value(i))
+    }
+
+    /// Iterates over the entries whose keys lie within `range`, in increasing order of key.
+    ///
+    /// Any `RangeBounds<TotalF64>` can be used, including a [`NumericRange`](crate::NumericRange).
+    pub fn range<R: RangeBounds<TotalF64>>(
+        &self,
+        range: R,
+    ) -> impl DoubleEndedIterator<Item = (TotalF64, &'a [u8])> + ExactSizeIterator + 'a {
+        let start = match range.start_bound() {
+            Bound::Included(s) => {
+                let s = s.normalise();
+                self.partition_point(|k| k < s)
+            }
+            Bound::Excluded(s) => {
+                let s = s.normalise();
+                self.partition_point(|k| k <= s)
+            }
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(e) => {
+                let e = e.normalise();
+                self.partition_point(|k| k <= e)
+            }
+            Bound::Excluded(e) => {
+                let e = e.normalise();
+                self.partition_point(|k| k < e)
+            }
+            Bound::Unbounded => self.count,
+        };
+
+        let reader = *self;
+        (start..end.max(start)).map(move |i| (reader.key(i), reader.value(i)))
+    }
+
+    /// Finds the number of keys for which `pred` holds, which must be a prefix of the keys.
+    ///
+    /// The sparse index narrows the search down to a single block before the keys are read.
+    fn partition_point(&self, pred: impl Fn(i64) -> bool) -> usize {
+        let (lo, hi) = if self.sparse.is_empty() {
+            (0, self.count)
+        } else {
+            let blocks = self.sparse.len() / self.kind.width();
+            let b = partition(0, blocks, |b| pred(self.raw_sparse_key(b)));
+            (
+                b.saturating_sub(1) * self.block_size,
+                (b * self.block_size).min(self.count),
+            )
+        };
+        partition(lo, hi, |i| pred(self.raw_key(i)))
+    }
+
+    fn offset(&self, i: usize) -> u64 {
+        read_u64(self.offsets, i * 8)
+    }
+
+    /// Reads the `i`th key as a normalised `TotalF64`
+    fn raw_key(&self, i: usize) -> i64 {
+        read_key(self.kind, self.keys, i)
+    }
+
+    fn raw_sparse_key(&self, b: usize) -> i64 {
+        read_key(self.kind, self.sparse, b)
+    }
+}
+
+/// Splits the next `len` bytes off the front of the file, `None` when the length overflowed
+fn split<'a>(rest: &mut &'a [u8], len: Option<usize>) -> Result<&'a [u8], IndexError> {
+    let len = len
+        .filter(|&len| len <= rest.len())
+        .ok_or(IndexError::Truncated)?;
+    let (section, tail) = rest.split_at(len);
+    *rest = tail;
+    Ok(section)
+}
+
+fn read_key(kind: KeyKind, keys: &[u8], i: usize) -> i64 {
+    match kind {
+        KeyKind::F64 => read_u64(keys, i * 8) as i64,
+        KeyKind::F32 => {
+            let key = TotalF32::from_normalised(read_u32(keys, i * 4) as i32);
+            TotalF64::from(key).normalise()
+        }
+    }
+}
+
+/// Binary searches `lo..hi` for the first index where `pred` fails
+pub(crate) fn partition(mut lo: usize, mut hi: usize, pred: impl Fn(usize) -> bool) -> usize {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{numeric_range, NearestExt, TieBreak};
+
+    fn keys() -> Vec<f64> {
+        vec![
+            f64::NEG_INFINITY,
+            -3.0,
+            -0.0,
+            0.0,
+            0.5,
+            1.0,
+            2.0,
+            10.0,
+            f64::NAN,
+        ]
+    }
+
+    fn fixed_index(block_size: u32) -> Vec<u8> {
+        let mut writer = IndexWriter::new(ValueLayout::Fixed(4)).block_size(block_size);
+        for (i, &k) in keys().iter().enumerate().rev() {
+            writer
+                .insert(TotalF64(k), &(i as u32).to_le_bytes())
+                .unwrap();
+        }
+        writer.finish()
+    }
+
+    fn value(bytes: &[u8]) -> u32 {
+        u32::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    #[test]
+    fn test_index_lookup() {
+        for block_size in [0, 1, 2, 3, 16] {
+            let bytes = fixed_index(block_size);
+            let reader = IndexReader::open(&bytes).unwrap();
+            reader.verify().unwrap();
+            assert_eq!(reader.len(), keys().len());
+
+            for (i, &k) in keys().iter().enumerate() {
+                assert_eq!(reader.get(TotalF64(k)).map(value), Some(i as u32));
+                assert_eq!(reader.key(i), TotalF64(k));
+            }
+            assert_eq!(reader.get(TotalF64(0.25)), None);
+            assert_eq!(reader.get(TotalF64(-f64::NAN)), None);
+        }
+    }
+
+    #[test]
+    fn test_index_range_and_nearest() {
+        let bytes = fixed_index(4);
+        let reader = IndexReader::open(&bytes).unwrap();
+
+        let values: Vec<u32> = reader
+            .range(numeric_range(0.0..2.0))
+            .map(|(_, v)| value(v))
+            .collect();
+        assert_eq!(values, [2, 3, 4, 5]);
+        assert_eq!(reader.range(TotalF64(10.0)..).len(), 2);
+        assert_eq!(
+            reader.range(..).next_back().map(|(k, _)| k.0.is_nan()),
+            Some(true)
+        );
+
+        assert_eq!(
+            reader.floor_entry(0.75).map(|(k, _)| k),
+            Some(TotalF64(0.5))
+        );
+        assert_eq!(
+            reader.ceiling_entry(0.75).map(|(k, _)| k),
+            Some(TotalF64(1.0))
+        );
+        assert_eq!(
+            reader
+                .nearest_entry(1.5, TieBreak::Higher)
+                .map(|(_, v)| value(v)),
+            Some(6)
+        );
+        assert_eq!(
+            reader.nearest_entry(1e300, TieBreak::Lower).map(|(k, _)| k),
+            Some(TotalF64(10.0))
+        );
+    }
+
+    #[test]
+    fn test_index_variable_f32() {
+        let mut writer = IndexWriter::new(ValueLayout::Variable).block_size(2);
+        writer.insert(TotalF32(0.1), b"first").unwrap();
+        writer.insert(TotalF32(-1.0), b"").unwrap();
+        writer.insert(TotalF32(2.5), b"third").unwrap();
+        writer.insert(TotalF32(0.1), b"replaced").unwrap();
+        let bytes = writer.finish();
+
+        let reader = IndexReader::open(&bytes).unwrap();
+        reader.verify().unwrap();
+        assert_eq!(reader.key_kind(), KeyKind::F32);
+        assert_eq!(reader.len(), 3);
+
+        // the widened key is found, not the nearest f64
+        assert_eq!(
+            reader.get(TotalF64::from(TotalF32(0.1))),
+            Some(&b"replaced"[..])
+        );
+        assert_eq!(reader.get(TotalF64(0.1)), None);
+        assert_eq!(reader.get(TotalF64(-1.0)), Some(&b""[..]));
+        assert_eq!(
+            reader.nearest_entry(2.0, TieBreak::Lower).map(|(_, v)| v),
+            Some(&b"third"[..])
+        );
+    }
+
+    #[test]
+    fn test_index_errors() {
+        let mut writer = IndexWriter::<TotalF64>::new(ValueLayout::Fixed(2));
+        assert_eq!(
+            writer.insert(TotalF64(1.0), b"abc"),
+            Err(IndexError::ValueSize {
+                expected: 2,
+                found: 3
+            })
+        );
+
+        let bytes = fixed_index(2);
+        assert_eq!(
+            IndexReader::open(&bytes[..20]).unwrap_err(),
+            IndexError::Truncated
+        );
+        assert_eq!(
+            IndexReader::open(&bytes[..bytes.len() - 1]).unwrap_err(),
+            IndexError::Truncated
+        );
+        assert_eq!(
+            IndexReader::open(b"nope").unwrap_err(),
+            IndexError::BadMagic
+        );
+
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        assert_eq!(
+            IndexReader::open(&newer).unwrap_err(),
+            IndexError::UnsupportedVersion(2)
+        );
+
+        let mut corrupt = bytes.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        let reader = IndexReader::open(&corrupt).unwrap();
+        assert_eq!(reader.verify(), Err(IndexError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn test_index_golden_header() {
+        let mut writer = IndexWriter::new(ValueLayout::Fixed(1));
+        writer.insert(TotalF64(1.0), &[7]).unwrap();
+        let bytes = writer.finish();
+
+        assert_eq!(bytes.len(), HEADER_LEN + 8 + 1);
+        assert_eq!(
+            &bytes[..24],
+            b"TFIX\x01\x00\x00\x00\x01\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"
+        );
+        assert_eq!(&bytes[HEADER_LEN..], &[0, 0, 0, 0, 0, 0, 0xf0, 0x3f, 7]);
+        assert_eq!(read_u64(&bytes, CHECKSUM_OFFSET), 0x6320_f31b_4ca1_8cab);
+    }
+}
@@ -0,0 +1,610 @@
+use core::marker::PhantomData;
+
+use crate::compressed_set::Reader;
+use crate::{DecodeError, TotalF32, TotalF64};
+
+/// The number of values in each block when no block length is given
+pub const DEFAULT_BLOCK_LEN: usize = 1024;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Float types which can be compressed by a [`GorillaEncoder`].
+///
+/// This trait is sealed, it is implemented for `TotalF64` and `TotalF32`.
+pub trait GorillaFloat: Copy + private::Sealed {
+    /// The width of the bit pattern.
+    #[doc(hidden)]
+    const BITS: u32;
+
+    /// The width of the field holding the number of leading zeros, which saturates.
+    #[doc(hidden)]
+    const LEADING_BITS: u32;
+
+    /// The width of the field holding the number of meaningful bits, which is never 0, so
+    /// `BITS` is written as 0.
+    #[doc(hidden)]
+    const LENGTH_BITS: u32;
+
+    /// The tag recorded in the encoding.
+    #[doc(hidden)]
+    const KIND: u8;
+
+    #[doc(hidden)]
+    fn to_raw(self) -> u64;
+
+    #[doc(hidden)]
+    fn from_raw(raw: u64) -> Self;
+}
+
+impl private::Sealed for TotalF64 {}
+
+impl GorillaFloat for TotalF64 {
+    const BITS: u32 = 64;
+    const LEADING_BITS: u32 = 5;
+    const LENGTH_BITS: u32 = 6;
+    const KIND: u8 = 0;
+
+    fn to_raw(self) -> u64 {
+        self.0.to_bits()
+    }
+
+    fn from_raw(raw: u64) -> Self {
+        TotalF64(f64::from_bits(raw))
+    }
+}
+
+impl private::Sealed for TotalF32 {}
+
+impl GorillaFloat for TotalF32 {
+    const BITS: u32 = 32;
+    const LEADING_BITS: u32 = 4;
+    const LENGTH_BITS: u32 = 5;
+    const KIND: u8 = 1;
+
+    fn to_raw(self) -> u64 {
+        self.0.to_bits() as u64
+    }
+
+    fn from_raw(raw: u64) -> Self {
+        TotalF32(f32::from_bits(raw as u32))
+    }
+}
+
+/// Appends bits to a byte vector, most significant bit first
+#[derive(Debug, Clone, Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// The number of bits used in the last byte, 0 when it is full
+    used: u32,
+}
+
+impl BitWriter {
+    /// Writes the low `bits` bits of `value`
+    fn write(&mut self, value: u64, mut bits: u32) {
+        while bits > 0 {
+            if self.used == 0 {
+                self.bytes.push(0);
+            }
+
+            let free = 8 - self.used;
+            let take = free.min(bits);
+            let chunk = (value >> (bits - take)) & ((1 << take) - 1);
+            *self.bytes.last_mut().unwrap() |= (chunk as u8) << (free - take);
+
+            self.used = (self.used + take) % 8;
+            bits -= take;
+        }
+    }
+
+    /// Pads the last byte with zeros, so that the next write starts a new byte
+    fn align(&mut self) {
+        self.used = 0;
+    }
+}
+
+/// Reads bits written by a `BitWriter`
+#[derive(Debug, Clone)]
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, mut bits: u32) -> Option<u64> {
+        let mut value = 0;
+        while bits > 0 {
+            let byte = *self.bytes.get(self.pos / 8)?;
+            let offset = (self.pos % 8) as u32;
+            let take = (8 - offset).min(bits);
+            let chunk = (byte >> (8 - offset - take)) & ((1 << take) - 1) as u8;
+
+            value = (value << take) | chunk as u64;
+            self.pos += take as usize;
+            bits -= take;
+        }
+        Some(value)
+    }
+}
+
+/// The state shared by the encoder and decoder, reset at the start of every block
+#[derive(Debug, Copy, Clone, Default)]
+struct XorState {
+    prev: u64,
+    /// The leading and trailing zeros of the current window of meaningful bits
+    window: Option<(u32, u32)>,
+}
+
+/// Compresses a stream of floats with the XOR scheme of Facebook's Gorilla.
+///
+/// Each value is XORed with the one before it. Identical values then take a single bit, and
+/// values which only differ in a few bits store just those, within a window of meaningful bits
+/// which is reused while it fits. Slowly changing series usually take a few bits per value.
+///
+/// The raw bit patterns are compressed, so every value round-trips exactly, including `-0.0`
+/// and the payloads of NaNs.
+/// The stream is split into blocks of a fixed number of values which start on a byte boundary
+/// with the state reset, so decoding can start from any block.
+///
+/// ```rust
+/// use total_float_wrap::{GorillaEncoder, TotalF64};
+///
+/// let mut encoder = GorillaEncoder::new();
+/// for i in 0..100 {
+///     encoder.push(TotalF64(20.0 + (i / 10) as f64 * 0.5));
+/// }
+/// let series = encoder.finish();
+///
+/// assert!(series.as_bytes().len() < 100);
+/// assert_eq!(series.iter().nth(42), Some(TotalF64(22.0)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct GorillaEncoder<T: GorillaFloat> {
+    writer: BitWriter,
+    state: XorState,
+    block_len: usize,
+    blocks: Vec<usize>,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: GorillaFloat> GorillaEncoder<T> {
+    /// Creates an encoder using blocks of [`DEFAULT_BLOCK_LEN`] values.
+    pub fn new() -> Self {
+        Self::with_block_len(DEFAULT_BLOCK_LEN)
+    }
+
+    /// Creates an encoder using blocks of `block_len` values.
+    ///
+    /// Shorter blocks allow finer seeking, at the cost of a raw value at the start of each.
+    /// Panics if `block_len` is 0, or too long to serialise as a `u32`.
+    pub fn with_block_len(block_len: usize) -> Self {
+        assert!(block_len > 0, "block length must be positive");
+        assert!(
+            u32::try_from(block_len).is_ok(),
+            "block length must fit in a u32"
+        );
+        Self {
+            writer: BitWriter::default(),
+            state: XorState::default(),
+            block_len,
+            blocks: Vec::new(),
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of values pushed so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no values have been pushed
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends a value to the stream
+    pub fn push(&mut self, value: T) {
+        let raw = value.to_raw();
+        let writer = &mut self.writer;
+
+        if self.len.is_multiple_of(self.block_len) {
+            writer.align();
+            self.blocks.push(writer.bytes.len());
+            writer.write(raw, T::BITS);
+            self.state = XorState {
+                prev: raw,
+                window: None,
+            };
+            self.len += 1;
+            return;
+        }
+
+        let xor = self.state.prev ^ raw;
+        self.state.prev = raw;
+        self.len += 1;
+        if xor == 0 {
+            writer.write(0, 1);
+            return;
+        }
+
+        let max_leading = (1 << T::LEADING_BITS) - 1;
+        let leading = (xor.leading_zeros() - (64 - T::BITS)).min(max_leading);
+        let trailing = xor.trailing_zeros();
+
+        match self.state.window {
+            Some((l, t)) if leading >= l && trailing >= t => {
+                writer.write(0b10, 2);
+                writer.write(xor >> t, T::BITS - l - t);
+            }
+            _ => {
+                let length = T::BITS - leading - trailing;
+                writer.write(0b11, 2);
+                writer.write(leading as u64, T::LEADING_BITS);
+                writer.write((length % T::BITS) as u64, T::LENGTH_BITS);
+                writer.write(xor >> trailing, length);
+                self.state.window = Some((leading, trailing));
+            }
+        }
+    }
+
+    /// Finishes the stream
+    pub fn finish(self) -> GorillaSeries<T> {
+        GorillaSeries {
+            bytes: self.writer.bytes,
+            block_len: self.block_len,
+            blocks: self.blocks,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: GorillaFloat> Default for GorillaEncoder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: GorillaFloat> Extend<T> for GorillaEncoder<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+/// A stream of floats compressed by a [`GorillaEncoder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GorillaSeries<T: GorillaFloat> {
+    bytes: Vec<u8>,
+    block_len: usize,
+    /// The offset of the first byte of each block
+    blocks: Vec<usize>,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: GorillaFloat> GorillaSeries<T> {
+    /// Returns the number of values in the series
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the series contains no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of values in each block, only the last block may be shorter
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Returns the number of blocks in the series
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns the compressed bit stream, without the block index
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Decodes the values from the start of the series
+    pub fn iter(&self) -> GorillaDecoder<'_, T> {
+        GorillaDecoder {
+            series: self,
+            reader: BitReader {
+                bytes: &self.bytes,
+                pos: 0,
+            },
+            state: XorState::default(),
+            index: 0,
+        }
+    }
+
+    /// Encodes the series, along with its block index, as bytes.
+    ///
+    /// The encoding is a kind byte, 0 for `f64` and 1 for `f32`, the little-endian `u32` block
+    /// length, `u64` number of values, a `u64` byte offset for each block and the `u64` length
+    /// of the bit stream, followed by the bit stream.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(21 + 8 * self.blocks.len() + self.bytes.len());
+        out.push(T::KIND);
+        out.extend_from_slice(&(self.block_len as u32).to_le_bytes());
+        out.extend_from_slice(&(self.len as u64).to_le_bytes());
+        for &block in &self.blocks {
+            out.extend_from_slice(&(block as u64).to_le_bytes());
+        }
+        out.extend_from_slice(&(self.bytes.len() as u64).to_le_bytes());
+        out.extend_from_slice(&self.bytes);
+        out
+    }
+
+    /// Decodes a series encoded by [`to_bytes`](Self::to_bytes), checking its structure.
+    ///
+    /// The bit stream itself is only checked as it is decoded, a corrupt stream ends early.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader(bytes);
+        let kind = reader.u8()?;
+        let block_len = reader.u32()? as usize;
+        let len = reader.usize()?;
+        if kind != T::KIND || block_len == 0 {
+            return Err(DecodeError::Invalid);
+        }
+
+        let blocks = (0..len.div_ceil(block_len))
+            .map(|_| reader.usize())
+            .collect::<Result<Vec<_>, _>>()?;
+        let stream_len = reader.usize()?;
+        let stream = reader.take(stream_len)?.to_vec();
+        reader.finish()?;
+
+        let increasing = blocks.windows(2).all(|w| w[0] < w[1]);
+        let in_bounds = blocks.last().is_none_or(|&last| last < stream.len());
+        if blocks.first().is_some_and(|&first| first != 0) || !increasing || !in_bounds {
+            return Err(DecodeError::Invalid);
+        }
+
+        Ok(Self {
+            bytes: stream,
+            block_len,
+            blocks,
+            len,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: GorillaFloat> FromIterator<T> for GorillaSeries<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut encoder = GorillaEncoder::new();
+        encoder.extend(iter);
+        encoder.finish()
+    }
+}
+
+impl<'a, T: GorillaFloat> IntoIterator for &'a GorillaSeries<T> {
+    type Item = T;
+    type IntoIter = GorillaDecoder<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Decodes the values of a [`GorillaSeries`] in order, and can jump to the start of any block.
+#[derive(Debug, Clone)]
+pub struct GorillaDecoder<'a, T: GorillaFloat> {
+    series: &'a GorillaSeries<T>,
+    reader: BitReader<'a>,
+    state: XorState,
+    /// The index of the next value
+    index: usize,
+}
+
+impl<T: GorillaFloat> GorillaDecoder<'_, T> {
+    /// Returns the index of the next value to be decoded
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Moves to the first value of block `block`, or to the end when there is no such block
+    pub fn seek_block(&mut self, block: usize) {
+        self.index = block
+            .saturating_mul(self.series.block_len)
+            .min(self.series.len);
+    }
+
+    /// Moves to the value at `index`, decoding from the start of its block.
+    pub fn seek(&mut self, index: usize) {
+        self.seek_block(index / self.series.block_len);
+        for _ in self.index..index.min(self.series.len) {
+            self.next();
+        }
+    }
+
+    fn decode(&mut self) -> Option<u64> {
+        let reader = &mut self.reader;
+        if self.index.is_multiple_of(self.series.block_len) {
+            reader.pos = self.series.blocks[self.index / self.series.block_len] * 8;
+            self.state = XorState {
+                prev: reader.read(T::BITS)?,
+                window: None,
+            };
+            return Some(self.state.prev);
+        }
+
+        if reader.read(1)? == 0 {
+            return Some(self.state.prev);
+        }
+
+        let (leading, trailing) = match (reader.read(1)?, self.state.window) {
+            (0, Some(window)) => window,
+            (0, None) => return None,
+            _ => {
+                let leading = reader.read(T::LEADING_BITS)? as u32;
+                let length = match reader.read(T::LENGTH_BITS)? as u32 {
+                    0 => T::BITS,
+                    length => length,
+                };
+                let window = (leading, T::BITS.checked_sub(leading + length)?);
+                self.state.window = Some(window);
+                window
+            }
+        };
+
+        let xor = reader.read(T::BITS - leading - trailing)? << trailing;
+        self.state.prev ^= xor;
+        Some(self.state.prev)
+    }
+}
+
+impl<T: GorillaFloat> Iterator for GorillaDecoder<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.index >= self.series.len {
+            return None;
+        }
+
+        match self.decode() {
+            Some(raw) => {
+                self.index += 1;
+                Some(T::from_raw(raw))
+            }
+            None => {
+                // the stream is corrupt, so stop rather than return garbage
+                self.index = self.series.len;
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.series.len - self.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits64(values: impl IntoIterator<Item = TotalF64>) -> Vec<u64> {
+        values.into_iter().map(|v| v.0.to_bits()).collect()
+    }
+
+    fn awkward_f64s() -> Vec<TotalF64> {
+        [
+            0.0,
+            -0.0,
+            -0.0,
+            f64::NAN,
+            f64::from_bits(f64::NAN.to_bits() | 0xdead_beef),
+            -f64::from_bits(f64::NAN.to_bits() | 1),
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::MIN_POSITIVE / 3.0,
+            1.0,
+            1.0,
+            1.0 + f64::EPSILON,
+            -12345.678,
+            f64::MAX,
+        ]
+        .map(TotalF64)
+        .into()
+    }
+
+    #[test]
+    fn test_gorilla_round_trips_bits() {
+        let values = awkward_f64s();
+        for block_len in [1, 2, 5, DEFAULT_BLOCK_LEN] {
+            let mut encoder = GorillaEncoder::with_block_len(block_len);
+            encoder.extend(values.iter().copied());
+            let series = encoder.finish();
+
+            assert_eq!(series.len(), values.len());
+            assert_eq!(series.block_count(), values.len().div_ceil(block_len));
+            assert_eq!(bits64(&series), bits64(values.iter().copied()));
+        }
+
+        let empty: GorillaSeries<TotalF64> = GorillaSeries::from_iter([]);
+        assert_eq!(empty.iter().next(), None);
+    }
+
+    #[test]
+    fn test_gorilla_f32() {
+        let values: Vec<TotalF32> = awkward_f64s()
+            .into_iter()
+            .map(|v| TotalF32(v.0 as f32))
+            .chain([TotalF32(f32::from_bits(0x7fc0_1234))])
+            .collect();
+        let series: GorillaSeries<TotalF32> = values.iter().copied().collect();
+
+        let decoded: Vec<u32> = series.iter().map(|v| v.0.to_bits()).collect();
+        let expected: Vec<u32> = values.iter().map(|v| v.0.to_bits()).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn test_gorilla_compresses_slow_series() {
+        let values = (0..10_000).map(|i| TotalF64(100.0 + (i / 60) as f64 * 0.25));
+        let series: GorillaSeries<TotalF64> = values.clone().collect();
+
+        // mostly repeats, so little more than a bit per value
+        assert!(
+            series.as_bytes().len() < 10_000 / 4,
+            "{}",
+            series.as_bytes().len()
+        );
+        assert!(series.iter().eq(values));
+    }
+
+    #[test]
+    fn test_gorilla_seek() {
+        let values: Vec<TotalF64> = (0..100).map(|i| TotalF64((i as f64).sin())).collect();
+        let mut encoder = GorillaEncoder::with_block_len(16);
+        encoder.extend(values.iter().copied());
+        let series = encoder.finish();
+
+        let mut decoder = series.iter();
+        decoder.seek_block(3);
+        assert_eq!(decoder.position(), 48);
+        assert_eq!(decoder.next(), Some(values[48]));
+
+        decoder.seek(37);
+        assert!(decoder.eq(values[37..].iter().copied()));
+
+        let mut decoder = series.iter();
+        decoder.seek_block(7);
+        assert_eq!(decoder.next(), None);
+        decoder.seek(0);
+        assert_eq!(decoder.next(), Some(values[0]));
+    }
+
+    #[test]
+    fn test_gorilla_bytes() {
+        let mut encoder = GorillaEncoder::with_block_len(4);
+        encoder.extend(awkward_f64s());
+        let series = encoder.finish();
+
+        let bytes = series.to_bytes();
+        assert_eq!(GorillaSeries::from_bytes(&bytes), Ok(series));
+        assert_eq!(
+            GorillaSeries::<TotalF32>::from_bytes(&bytes),
+            Err(DecodeError::Invalid)
+        );
+        assert_eq!(
+            GorillaSeries::<TotalF64>::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::Truncated)
+        );
+
+        // a corrupt stream ends early instead of panicking
+        let mut corrupt = GorillaSeries::<TotalF64>::from_bytes(&bytes).unwrap();
+        corrupt.bytes.truncate(corrupt.blocks[1] + 1);
+        assert!(corrupt.iter().count() < corrupt.len());
+    }
+}
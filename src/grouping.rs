@@ -0,0 +1,352 @@
+use core::cmp::Reverse;
+
+use crate::{FloatMap, TotalF64};
+
+/// Which float values are counted or grouped together.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum EqualityPolicy {
+    /// Values are equal when they are equal under the total order, as for `TotalF64`, so `-0.0`
+    /// and `0.0` are distinct, as are NaNs with different signs or payloads.
+    #[default]
+    Total,
+    /// Values are equal when they are numerically equal, except that every NaN is equal to every
+    /// other. `-0.0` is stored as `0.0`, and every NaN as `f64::NAN`.
+    Numeric,
+}
+
+impl EqualityPolicy {
+    /// Returns the key which represents the value under this policy
+    pub fn key(self, value: impl Into<TotalF64>) -> TotalF64 {
+        let value = value.into();
+        match self {
+            EqualityPolicy::Total => value,
+            EqualityPolicy::Numeric if value.0.is_nan() => TotalF64(f64::NAN),
+            EqualityPolicy::Numeric if value.0 == 0.0 => TotalF64(0.0),
+            EqualityPolicy::Numeric => value,
+        }
+    }
+}
+
+/// Counts occurrences of float values, a multiset keyed by `TotalF64`.
+///
+/// ```rust
+/// use total_float_wrap::{EqualityPolicy, FloatCounter, TotalF64};
+///
+/// let counter: FloatCounter = [0.5, 2.0, 0.5, -0.0, 0.0].into_iter().collect();
+/// assert_eq!(counter.most_common(1), [(TotalF64(0.5), 2)]);
+///
+/// let mut numeric = FloatCounter::with_policy(EqualityPolicy::Numeric);
+/// numeric.extend([0.5, 2.0, 0.5, -0.0, 0.0]);
+/// assert_eq!(numeric.mode(), [TotalF64(0.0), TotalF64(0.5)]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FloatCounter {
+    counts: FloatMap<usize>,
+    policy: EqualityPolicy,
+    total: usize,
+}
+
+impl FloatCounter {
+    /// Creates an empty counter which uses the total equality policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a counter which counts values as equal according to `policy`.
+    pub fn with_policy(policy: EqualityPolicy) -> Self {
+        Self {
+            policy,
+            ..Self::default()
+        }
+    }
+
+    /// Counts one more occurrence of the value, returning its new count
+    pub fn add(&mut self, value: impl Into<TotalF64>) -> usize {
+        self.add_n(value, 1)
+    }
+
+    /// Counts `n` more occurrences of the value, returning its new count.
+    ///
+    /// Adding zero occurrences leaves an unseen value out of the counter.
+    pub fn add_n(&mut self, value: impl Into<TotalF64>, n: usize) -> usize {
+        if n == 0 {
+            return self.count(value);
+        }
+        self.total += n;
+        let count = self.counts.entry(self.policy.key(value)).or_default();
+        *count += n;
+        *count
+    }
+
+    /// Returns the number of occurrences of the value
+    pub fn count(&self, value: impl Into<TotalF64>) -> usize {
+        self.counts
+            .get(&self.policy.key(value))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of distinct values
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns whether no values have been counted
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the number of occurrences of every value
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns up to `n` values with their counts, most common first.
+    ///
+    /// Values with equal counts are ordered by the total order, so the result is deterministic,
+    /// but a tie may be split by the cut off. [`mode`](FloatCounter::mode) returns every tied
+    /// value.
+    pub fn most_common(&self, n: usize) -> Vec<(TotalF64, usize)> {
+        let mut counts: Vec<(TotalF64, usize)> =
+            self.counts.iter().map(|(&k, &c)| (k, c)).collect();
+        counts.sort_unstable_by_key(|&(k, c)| (Reverse(c), k));
+        counts.truncate(n);
+        counts
+    }
+
+    /// Returns every value with the highest count, in total order
+    pub fn mode(&self) -> Vec<TotalF64> {
+        let Some(&max) = self.counts.values().max() else {
+            return Vec::new();
+        };
+
+        let mut modes: Vec<TotalF64> = self
+            .counts
+            .iter()
+            .filter(|&(_, &c)| c == max)
+            .map(|(&k, _)| k)
+            .collect();
+        modes.sort_unstable();
+        modes
+    }
+
+    /// Iterates over the values and their counts, in total order
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (TotalF64, usize)> {
+        let mut counts: Vec<(TotalF64, usize)> =
+            self.counts.iter().map(|(&k, &c)| (k, c)).collect();
+        counts.sort_unstable_by_key(|&(k, _)| k);
+        counts.into_iter()
+    }
+}
+
+impl<K: Into<TotalF64>> Extend<K> for FloatCounter {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+impl<K: Into<TotalF64>> FromIterator<K> for FloatCounter {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut counter = Self::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+/// A multimap grouping items by a float key.
+///
+/// ```rust
+/// use total_float_wrap::{FloatGroups, TotalF64};
+///
+/// let points = [(1, 1), (2, 2), (1, 2), (3, 3)];
+/// let groups = FloatGroups::group_by_float(points, |&(x, y)| f64::atan2(y as f64, x as f64));
+///
+/// let largest = groups.largest_groups();
+/// assert_eq!(largest.len(), 1);
+/// assert_eq!(largest[0].1, [(1, 1), (2, 2), (3, 3)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FloatGroups<T> {
+    groups: FloatMap<Vec<T>>,
+    policy: EqualityPolicy,
+}
+
+impl<T> FloatGroups<T> {
+    /// Creates an empty multimap which uses the total equality policy.
+    pub fn new() -> Self {
+        Self::with_policy(EqualityPolicy::default())
+    }
+
+    /// Creates a multimap which groups keys as equal according to `policy`.
+    pub fn with_policy(policy: EqualityPolicy) -> Self {
+        Self {
+            groups: FloatMap::default(),
+            policy,
+        }
+    }
+
+    /// Groups the items by the key computed for each of them, under the total order.
+    pub fn group_by_float<K: Into<TotalF64>>(
+        items: impl IntoIterator<Item = T>,
+        key_fn: impl FnMut(&T) -> K,
+    ) -> Self {
+        Self::group_by_float_with(EqualityPolicy::default(), items, key_fn)
+    }
+
+    /// Groups the items by the key computed for each of them, with keys equal according to
+    /// `policy`.
+    pub fn group_by_float_with<K: Into<TotalF64>>(
+        policy: EqualityPolicy,
+        items: impl IntoIterator<Item = T>,
+        mut key_fn: impl FnMut(&T) -> K,
+    ) -> Self {
+        let mut groups = Self::with_policy(policy);
+        for item in items {
+            groups.insert(key_fn(&item), item);
+        }
+        groups
+    }
+
+    /// Adds an item to the group of the key
+    pub fn insert(&mut self, key: impl Into<TotalF64>, item: T) {
+        self.groups
+            .entry(self.policy.key(key))
+            .or_default()
+            .push(item);
+    }
+
+    /// Returns the items in the group of the key, in the order they were added
+    pub fn get(&self, key: impl Into<TotalF64>) -> Option<&[T]> {
+        self.groups.get(&self.policy.key(key)).map(Vec::as_slice)
+    }
+
+    /// Returns the number of groups
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns whether there are no groups
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Returns every group with the most items, in total order of their keys
+    pub fn largest_groups(&self) -> Vec<(TotalF64, &[T])> {
+        let Some(max) = self.groups.values().map(Vec::len).max() else {
+            return Vec::new();
+        };
+
+        let mut largest: Vec<(TotalF64, &[T])> = self
+            .groups
+            .iter()
+            .filter(|(_, items)| items.len() == max)
+            .map(|(&k, items)| (k, items.as_slice()))
+            .collect();
+        largest.sort_unstable_by_key(|&(k, _)| k);
+        largest
+    }
+
+    /// Iterates over the groups, in total order of their keys
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (TotalF64, &[T])> {
+        let mut groups: Vec<(TotalF64, &[T])> = self
+            .groups
+            .iter()
+            .map(|(&k, items)| (k, items.as_slice()))
+            .collect();
+        groups.sort_unstable_by_key(|&(k, _)| k);
+        groups.into_iter()
+    }
+}
+
+impl<T> Default for FloatGroups<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equality_policy() {
+        let payload = -f64::from_bits(f64::NAN.to_bits() | 3);
+        assert_eq!(
+            EqualityPolicy::Total.key(-0.0).0.to_bits(),
+            (-0.0_f64).to_bits()
+        );
+        assert_eq!(EqualityPolicy::Numeric.key(-0.0), TotalF64(0.0));
+        assert_eq!(EqualityPolicy::Numeric.key(payload), TotalF64(f64::NAN));
+        assert_eq!(EqualityPolicy::Numeric.key(2_u8), TotalF64(2.0));
+    }
+
+    #[test]
+    fn test_float_counter() {
+        let values = [1.0, 2.0, 2.0, f64::NAN, -f64::NAN, 1.0, 3.0];
+        let counter: FloatCounter = values.into_iter().collect();
+
+        assert_eq!(counter.total(), 7);
+        assert_eq!(counter.len(), 5);
+        assert_eq!(counter.count(2.0), 2);
+        assert_eq!(counter.count(f64::NAN), 1);
+        assert_eq!(counter.count(4.0), 0);
+        assert_eq!(counter.mode(), [TotalF64(1.0), TotalF64(2.0)]);
+        assert_eq!(
+            counter.most_common(3),
+            [
+                (TotalF64(1.0), 2),
+                (TotalF64(2.0), 2),
+                (TotalF64(-f64::NAN), 1)
+            ]
+        );
+
+        let mut numeric = FloatCounter::with_policy(EqualityPolicy::Numeric);
+        numeric.extend(values);
+        assert_eq!(numeric.len(), 4);
+        assert_eq!(numeric.count(-f64::NAN), 2);
+        assert_eq!(
+            numeric.mode(),
+            [TotalF64(1.0), TotalF64(2.0), TotalF64(f64::NAN)]
+        );
+
+        let sorted: Vec<f64> = numeric.iter_sorted().map(|(k, _)| k.0).collect();
+        assert_eq!(sorted[..3], [1.0, 2.0, 3.0]);
+        assert!(FloatCounter::new().mode().is_empty());
+
+        let mut counter = FloatCounter::new();
+        assert_eq!(counter.add_n(5.0, 0), 0);
+        assert!(counter.is_empty());
+        assert!(counter.most_common(1).is_empty());
+        counter.add_n(5.0, 3);
+        assert_eq!(counter.add_n(5.0, 0), 3);
+        assert_eq!((counter.len(), counter.total()), (1, 3));
+    }
+
+    #[test]
+    fn test_float_groups() {
+        let items = [("a", 0.0), ("b", -0.0), ("c", 0.5), ("d", 0.0), ("e", 0.5)];
+        let groups = FloatGroups::group_by_float(items, |&(_, k)| k);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups.get(0.0), Some(&[("a", 0.0), ("d", 0.0)][..]));
+        assert_eq!(groups.get(1.0), None);
+
+        let largest: Vec<TotalF64> = groups
+            .largest_groups()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(largest, [TotalF64(0.0), TotalF64(0.5)]);
+
+        let keys: Vec<TotalF64> = groups.iter_sorted().map(|(k, _)| k).collect();
+        assert_eq!(keys, [TotalF64(-0.0), TotalF64(0.0), TotalF64(0.5)]);
+
+        let numeric = FloatGroups::group_by_float_with(EqualityPolicy::Numeric, items, |&(_, k)| k);
+        assert_eq!(numeric.len(), 2);
+        let largest = numeric.largest_groups();
+        assert_eq!(largest.len(), 1);
+        assert_eq!(largest[0].1.len(), 3);
+    }
+}
@@ -0,0 +1,453 @@
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::{RoundingMode, TotalF32, TotalF64};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Primitive integer types which the float wrappers can be compared against exactly.
+///
+/// This trait is sealed, it is implemented for every primitive integer type.
+pub trait Integer: Copy + private::Sealed {
+    /// Compares `f` against `int` by exact mathematical value.
+    #[doc(hidden)]
+    fn f64_cmp(f: f64, int: Self) -> Ordering;
+
+    /// Converts an integral float to the integer, `None` if it is out of range.
+    #[doc(hidden)]
+    fn from_integral_f64(f: f64) -> Option<Self>;
+
+    /// Converts a float by truncating it, saturating at the bounds of the integer.
+    #[doc(hidden)]
+    fn saturating_from_f64(f: f64) -> Self;
+}
+
+/// Error returned when a float cannot be converted to an integer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IntConversionError {
+    /// The value has a fractional part and exact conversion was requested.
+    Fractional,
+    /// The value is an infinity, or is beyond the bounds of the integer type.
+    OutOfRange,
+    /// The value is a NaN.
+    NaN,
+}
+
+impl fmt::Display for IntConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IntConversionError::Fractional => "value has a fractional part",
+            IntConversionError::OutOfRange => "value is out of range of the integer type",
+            IntConversionError::NaN => "value is NaN",
+        })
+    }
+}
+
+impl core::error::Error for IntConversionError {}
+
+/// 2^127, the first float above the range of i128
+const I128_LIMIT: f64 = 170141183460469231731687303715884105728.0;
+
+/// 2^128, the first float above the range of u128
+const U128_LIMIT: f64 = 340282366920938463463374607431768211456.0;
+
+/// Places NaNs below or above every integer according to their sign, as totalOrder does.
+fn nan_cmp(f: f64) -> Ordering {
+    if f.is_sign_negative() {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+/// Compares a float and an integer which the float has been truncated to.
+///
+/// If the truncated value matches the integer then the discarded fraction decides.
+/// Converting `t` back is exact: either `|f| < 2^53` so `t` is small, or `f` was already integral.
+fn fraction_cmp(f: f64, t: f64) -> Ordering {
+    if f > t {
+        Ordering::Greater
+    } else if f < t {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+
+fn f64_cmp_i128(f: f64, int: i128) -> Ordering {
+    if f.is_nan() {
+        nan_cmp(f)
+    } else if f >= I128_LIMIT {
+        Ordering::Greater
+    } else if f < -I128_LIMIT {
+        Ordering::Less
+    } else {
+        // in range, so `as` truncates towards zero without saturating
+        let t = f as i128;
+        t.cmp(&int).then_with(|| fraction_cmp(f, t as f64))
+    }
+}
+
+fn f64_cmp_u128(f: f64, int: u128) -> Ordering {
+    if f.is_nan() {
+        nan_cmp(f)
+    } else if f >= U128_LIMIT {
+        Ordering::Greater
+    } else if f < 0.0 {
+        Ordering::Less
+    } else {
+        let t = f as u128;
+        t.cmp(&int).then_with(|| fraction_cmp(f, t as f64))
+    }
+}
+
+/// Rounds a finite float to an integral value, `None` if `mode` is exact and the value is not
+/// already integral.
+fn round_integral(f: f64, mode: RoundingMode) -> Option<f64> {
+    // 2^52, every float at least this large in magnitude is integral
+    if f.abs() >= 4503599627370496.0 {
+        return Some(f);
+    }
+
+    // all of this is exact: the truncated value fits in an i64 and the fraction in an f64
+    let t = f as i64;
+    let fraction = f - t as f64;
+    if fraction == 0.0 {
+        return Some(f);
+    }
+
+    let away = if fraction > 0.0 { t + 1 } else { t - 1 };
+    let rounded = match mode {
+        RoundingMode::NearestEven if fraction.abs() == 0.5 && t % 2 != 0 => away,
+        RoundingMode::NearestEven if fraction.abs() > 0.5 => away,
+        RoundingMode::NearestEven => t,
+        RoundingMode::TowardPositive if fraction > 0.0 => away,
+        RoundingMode::TowardNegative if fraction < 0.0 => away,
+        RoundingMode::TowardPositive | RoundingMode::TowardNegative | RoundingMode::TowardZero => t,
+        RoundingMode::Exact => return None,
+    };
+
+    Some(rounded as f64)
+}
+
+impl TotalF64 {
+    /// Compares the value against an integer by exact mathematical value.
+    ///
+    /// Both zeros are equal to `0`, and NaNs are placed below or above every integer
+    /// according to their sign, consistently with the total order.
+    pub fn cmp_int<I: Integer>(&self, other: I) -> Ordering {
+        I::f64_cmp(self.0, other)
+    }
+
+    /// Converts to an integer by truncating towards zero, saturating at the bounds of `I`.
+    ///
+    /// Unlike `as`, NaNs saturate according to their sign rather than becoming `0`, so the
+    /// conversion is monotone with respect to the total order.
+    pub fn to_int_saturating<I: Integer>(self) -> I {
+        I::saturating_from_f64(self.0)
+    }
+
+    /// Rounds to an integer according to `mode`.
+    ///
+    /// Fails if the value is NaN, if the rounded value is out of the range of `I`, or if `mode`
+    /// is [`RoundingMode::Exact`] and the value is not integral.
+    pub fn to_int_rounded<I: Integer>(self, mode: RoundingMode) -> Result<I, IntConversionError> {
+        if self.0.is_nan() {
+            return Err(IntConversionError::NaN);
+        }
+
+        let rounded = round_integral(self.0, mode).ok_or(IntConversionError::Fractional)?;
+        I::from_integral_f64(rounded).ok_or(IntConversionError::OutOfRange)
+    }
+}
+
+impl TotalF32 {
+    /// Compares the value against an integer by exact mathematical value.
+    ///
+    /// Both zeros are equal to `0`, and NaNs are placed below or above every integer
+    /// according to their sign, consistently with the total order.
+    pub fn cmp_int<I: Integer>(&self, other: I) -> Ordering {
+        TotalF64::from(*self).cmp_int(other)
+    }
+
+    /// Converts to an integer by truncating towards zero, saturating at the bounds of `I`.
+    ///
+    /// Unlike `as`, NaNs saturate according to their sign rather than becoming `0`, so the
+    /// conversion is monotone with respect to the total order.
+    pub fn to_int_saturating<I: Integer>(self) -> I {
+        TotalF64::from(self).to_int_saturating()
+    }
+
+    /// Rounds to an integer according to `mode`.
+    ///
+    /// Fails if the value is NaN, if the rounded value is out of the range of `I`, or if `mode`
+    /// is [`RoundingMode::Exact`] and the value is not integral.
+    pub fn to_int_rounded<I: Integer>(self, mode: RoundingMode) -> Result<I, IntConversionError> {
+        TotalF64::from(self).to_int_rounded(mode)
+    }
+}
+
+macro_rules! impl_integer {
+    ($cmp:ident as $wide:ty: $($int:ty),*) => {$(
+        impl private::Sealed for $int {}
+
+        impl Integer for $int {
+            fn f64_cmp(f: f64, int: Self) -> Ordering {
+                $cmp(f, int as $wide)
+            }
+
+            fn from_integral_f64(f: f64) -> Option<Self> {
+                if Self::f64_cmp(f, Self::MIN).is_lt() || Self::f64_cmp(f, Self::MAX).is_gt() {
+                    None
+                } else {
+                    Some(f as Self)
+                }
+            }
+
+            fn saturating_from_f64(f: f64) -> Self {
+                match f {
+                    f if f.is_nan() && f.is_sign_negative() => Self::MIN,
+                    f if f.is_nan() => Self::MAX,
+                    f => f as Self,
+                }
+            }
+        }
+
+        impl TryFrom<TotalF64> for $int {
+            type Error = IntConversionError;
+
+            /// Succeeds only if the value is integral and in range
+            fn try_from(f: TotalF64) -> Result<Self, Self::Error> {
+                f.to_int_rounded(RoundingMode::Exact)
+            }
+        }
+
+        impl TryFrom<TotalF32> for $int {
+            type Error = IntConversionError;
+
+            /// Succeeds only if the value is integral and in range
+            fn try_from(f: TotalF32) -> Result<Self, Self::Error> {
+                f.to_int_rounded(RoundingMode::Exact)
+            }
+        }
+
+        impl_integer!(@cmp TotalF64, $int);
+        impl_integer!(@cmp TotalF32, $int);
+    )*};
+    (@from $float:ident($inner:ty): $($int:ty),*) => {$(
+        impl From<$int> for $float {
+            /// The conversion is lossless
+            fn from(i: $int) -> Self {
+                $float(<$inner>::from(i))
+            }
+        }
+    )*};
+    (@cmp $float:ty, $int:ty) => {
+        impl PartialEq<$int> for $float {
+            fn eq(&self, other: &$int) -> bool {
+                self.cmp_int(*other).is_eq()
+            }
+        }
+
+        impl PartialOrd<$int> for $float {
+            fn partial_cmp(&self, other: &$int) -> Option<Ordering> {
+                Some(self.cmp_int(*other))
+            }
+        }
+
+        impl PartialEq<$float> for $int {
+            fn eq(&self, other: &$float) -> bool {
+                other.cmp_int(*self).is_eq()
+            }
+        }
+
+        impl PartialOrd<$float> for $int {
+            fn partial_cmp(&self, other: &$float) -> Option<Ordering> {
+                Some(other.cmp_int(*self).reverse())
+            }
+        }
+    };
+}
+
+impl_integer!(f64_cmp_i128 as i128: i8, i16, i32, i64, i128, isize);
+impl_integer!(f64_cmp_u128 as u128: u8, u16, u32, u64, u128, usize);
+
+impl_integer!(@from TotalF64(f64): i8, i16, i32, u8, u16, u32);
+impl_integer!(@from TotalF32(f32): i8, i16, u8, u16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmp_int_large_values() {
+        // 1e19 as i64 saturates to i64::MAX, which would make these compare equal
+        assert_eq!(Ordering::Greater, TotalF64(1e19).cmp_int(i64::MAX));
+        assert!(TotalF64(1e19) > i64::MAX);
+        assert!(TotalF64(1e19) < u64::MAX);
+
+        // u64::MAX as f64 rounds up to 2^64
+        let two_64 = TotalF64(u64::MAX as f64);
+        assert_eq!(Ordering::Greater, two_64.cmp_int(u64::MAX));
+        assert_ne!(two_64, u64::MAX);
+        assert_eq!(two_64, 1_u128 << 64);
+
+        assert_eq!(TotalF64(i64::MIN as f64), i64::MIN);
+        assert_eq!(TotalF64(-I128_LIMIT), i128::MIN);
+        assert!(TotalF64(I128_LIMIT) > i128::MAX);
+        assert!(TotalF64(U128_LIMIT) > u128::MAX);
+        assert!(TotalF64(f64::MAX) > u128::MAX);
+        assert!(TotalF64(f64::MIN) < i128::MIN);
+    }
+
+    #[test]
+    fn test_cmp_int_fractions() {
+        assert_eq!(Ordering::Greater, TotalF64(2.5).cmp_int(2));
+        assert_eq!(Ordering::Less, TotalF64(2.5).cmp_int(3));
+        assert_eq!(Ordering::Less, TotalF64(-2.5).cmp_int(-2));
+        assert_eq!(Ordering::Greater, TotalF64(-2.5).cmp_int(-3));
+        assert_eq!(Ordering::Less, TotalF64(-0.5).cmp_int(0_u8));
+        assert_eq!(Ordering::Greater, TotalF64(f64::MIN_POSITIVE).cmp_int(0));
+        assert_eq!(Ordering::Greater, TotalF32(16777217.0).cmp_int(16777215));
+        assert!(3 > TotalF32(2.75));
+        assert!(-3 < TotalF32(-2.75));
+    }
+
+    #[test]
+    fn test_cmp_int_special_values() {
+        assert_eq!(TotalF64(0.0), 0);
+        assert_eq!(TotalF64(-0.0), 0_u64);
+        assert_eq!(TotalF32(-0.0), 0_i128);
+        assert_eq!(
+            Ordering::Greater,
+            TotalF64(f64::INFINITY).cmp_int(u128::MAX)
+        );
+        assert_eq!(
+            Ordering::Less,
+            TotalF64(f64::NEG_INFINITY).cmp_int(i128::MIN)
+        );
+        assert_eq!(Ordering::Greater, TotalF64(f64::NAN).cmp_int(u128::MAX));
+        assert_eq!(Ordering::Less, TotalF64(-f64::NAN).cmp_int(i128::MIN));
+        assert_eq!(Ordering::Less, TotalF32(-f32::NAN).cmp_int(0_u8));
+        assert_ne!(TotalF64(f64::NAN), 0);
+    }
+
+    #[test]
+    fn test_from_small_ints() {
+        assert_eq!(TotalF64::from(i32::MIN).0, -2147483648.0);
+        assert_eq!(TotalF64::from(u32::MAX).0, 4294967295.0);
+        assert_eq!(TotalF32::from(i16::MIN).0, -32768.0);
+        assert_eq!(TotalF32::from(u16::MAX).0, 65535.0);
+    }
+
+    #[test]
+    fn test_try_from_float() {
+        assert_eq!(i64::try_from(TotalF64(-42.0)), Ok(-42));
+        assert_eq!(u8::try_from(TotalF64(-0.0)), Ok(0));
+        assert_eq!(
+            u64::try_from(TotalF64(1e19)),
+            Ok(10_000_000_000_000_000_000)
+        );
+        assert_eq!(
+            i64::try_from(TotalF64(1e19)),
+            Err(IntConversionError::OutOfRange)
+        );
+        assert_eq!(i64::try_from(TotalF64(i64::MIN as f64)), Ok(i64::MIN));
+        assert_eq!(
+            i64::try_from(TotalF64(i64::MAX as f64)),
+            Err(IntConversionError::OutOfRange)
+        );
+        assert_eq!(
+            u8::try_from(TotalF64(256.0)),
+            Err(IntConversionError::OutOfRange)
+        );
+        assert_eq!(
+            u8::try_from(TotalF64(-1.0)),
+            Err(IntConversionError::OutOfRange)
+        );
+        assert_eq!(
+            i32::try_from(TotalF64(0.5)),
+            Err(IntConversionError::Fractional)
+        );
+        assert_eq!(
+            i32::try_from(TotalF64(f64::INFINITY)),
+            Err(IntConversionError::OutOfRange)
+        );
+        assert_eq!(
+            i32::try_from(TotalF64(f64::NAN)),
+            Err(IntConversionError::NaN)
+        );
+        assert_eq!(u128::try_from(TotalF32(f32::MAX)), Ok(f32::MAX as u128));
+        assert_eq!(
+            i128::try_from(TotalF32(f32::MAX)),
+            Err(IntConversionError::OutOfRange)
+        );
+        assert_eq!(
+            u16::try_from(TotalF32(1.25)),
+            Err(IntConversionError::Fractional)
+        );
+    }
+
+    #[test]
+    fn test_to_int_saturating() {
+        assert_eq!(TotalF64(1e300).to_int_saturating::<i32>(), i32::MAX);
+        assert_eq!(TotalF64(-7.9).to_int_saturating::<i32>(), -7);
+        assert_eq!(TotalF64(-7.9).to_int_saturating::<u32>(), 0);
+        assert_eq!(TotalF64(f64::NAN).to_int_saturating::<i8>(), i8::MAX);
+        assert_eq!(TotalF64(-f64::NAN).to_int_saturating::<i8>(), i8::MIN);
+        assert_eq!(
+            TotalF32(f32::NEG_INFINITY).to_int_saturating::<i64>(),
+            i64::MIN
+        );
+    }
+
+    #[test]
+    fn test_to_int_rounded() {
+        use RoundingMode::*;
+
+        let cases: [(f64, [i64; 4]); 7] = [
+            // value, [nearest even, toward positive, toward negative, toward zero]
+            (2.5, [2, 3, 2, 2]),
+            (3.5, [4, 4, 3, 3]),
+            (-2.5, [-2, -2, -3, -2]),
+            (-3.5, [-4, -3, -4, -3]),
+            (2.4, [2, 3, 2, 2]),
+            (-2.6, [-3, -2, -3, -2]),
+            (-0.2, [0, 0, -1, 0]),
+        ];
+
+        for (f, expected) in cases {
+            for (mode, e) in [NearestEven, TowardPositive, TowardNegative, TowardZero]
+                .into_iter()
+                .zip(expected)
+            {
+                assert_eq!(
+                    TotalF64(f).to_int_rounded::<i64>(mode),
+                    Ok(e),
+                    "{f} {mode:?}"
+                );
+            }
+            assert_eq!(
+                TotalF64(f).to_int_rounded::<i64>(Exact),
+                Err(IntConversionError::Fractional)
+            );
+        }
+
+        assert_eq!(TotalF64(255.5).to_int_rounded::<u8>(TowardZero), Ok(255));
+        assert_eq!(
+            TotalF64(255.5).to_int_rounded::<u8>(NearestEven),
+            Err(IntConversionError::OutOfRange)
+        );
+        assert_eq!(TotalF64(-0.5).to_int_rounded::<u8>(NearestEven), Ok(0));
+        assert_eq!(
+            TotalF64(1e20).to_int_rounded::<u128>(Exact),
+            Ok(100_000_000_000_000_000_000)
+        );
+        assert_eq!(
+            TotalF64(f64::NAN).to_int_rounded::<u8>(TowardZero),
+            Err(IntConversionError::NaN)
+        );
+    }
+}
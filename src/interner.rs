@@ -0,0 +1,239 @@
+use core::hash::Hash;
+use std::collections::HashMap;
+
+use crate::{FloatBuildHasher, TotalF64};
+
+/// Assigns dense `u32` ids to distinct float values, in the order they are first seen.
+///
+/// Values are distinguished by the total order, so `-0.0` and `0.0`, and NaNs with different
+/// payloads, get different ids. `K` is usually `TotalF64` or `TotalF32`.
+///
+/// Once every value has been interned, [`finish`](FloatInterner::finish) relabels the ids so that
+/// they are in the total order of their values.
+///
+/// ```rust
+/// use total_float_wrap::{FloatInterner, TotalF64};
+///
+/// let mut interner = FloatInterner::new();
+/// let ids = [2.5, -1.0, 2.5].map(|v| interner.intern(TotalF64(v)));
+/// assert_eq!(ids, [0, 1, 0]);
+///
+/// let (dictionary, relabel) = interner.finish();
+/// let sorted = ids.map(|id| relabel[id as usize]);
+/// assert_eq!(sorted, [1, 0, 1]);
+/// assert_eq!(dictionary.resolve(0), Some(TotalF64(-1.0)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FloatInterner<K = TotalF64> {
+    ids: HashMap<K, u32, FloatBuildHasher>,
+    values: Vec<K>,
+}
+
+impl<K: Copy + Ord + Hash> FloatInterner<K> {
+    /// Creates an empty interner
+    pub fn new() -> Self {
+        Self {
+            ids: HashMap::default(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Returns the number of distinct values interned
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns whether no values have been interned
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the id of the value, assigning the next id if it has not been seen before.
+    ///
+    /// Panics if more than `u32::MAX + 1` distinct values are interned.
+    pub fn intern(&mut self, value: K) -> u32 {
+        if let Some(&id) = self.ids.get(&value) {
+            return id;
+        }
+
+        let id = u32::try_from(self.values.len()).expect("more than 2^32 distinct values");
+        self.ids.insert(value, id);
+        self.values.push(value);
+        id
+    }
+
+    /// Returns the id of the value, if it has been interned
+    pub fn get(&self, value: K) -> Option<u32> {
+        self.ids.get(&value).copied()
+    }
+
+    /// Returns the value with the given id
+    pub fn resolve(&self, id: u32) -> Option<K> {
+        self.values.get(id as usize).copied()
+    }
+
+    /// Sorts the values into a [`FloatDictionary`], whose ids compare like their values.
+    ///
+    /// Also returns the new id of every old id, indexed by the old id, so that ids handed out
+    /// by the interner can be rewritten.
+    pub fn finish(self) -> (FloatDictionary<K>, Vec<u32>) {
+        // `intern` hands out every id up to `u32::MAX`, so count in `usize` where the length fits
+        let mut order: Vec<u32> = (0..self.values.len()).map(|id| id as u32).collect();
+        order.sort_unstable_by_key(|&id| self.values[id as usize]);
+
+        let mut relabel = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            relabel[old as usize] = new as u32;
+        }
+
+        let values = order.iter().map(|&id| self.values[id as usize]).collect();
+        (FloatDictionary { values }, relabel)
+    }
+}
+
+impl<K: Copy + Ord + Hash> Default for FloatInterner<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The distinct values of a [`FloatInterner`] in total order, so that ids compare like values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FloatDictionary<K = TotalF64> {
+    values: Vec<K>,
+}
+
+impl<K: Copy + Ord> FloatDictionary<K> {
+    /// Returns the number of values in the dictionary
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns whether the dictionary contains no values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the value with the given id
+    pub fn resolve(&self, id: u32) -> Option<K> {
+        self.values.get(id as usize).copied()
+    }
+
+    /// Returns the id of the value, found by binary search
+    pub fn get(&self, value: K) -> Option<u32> {
+        self.values.binary_search(&value).ok().map(|id| id as u32)
+    }
+
+    /// Returns the values, in increasing order of id
+    pub fn values(&self) -> &[K] {
+        &self.values
+    }
+}
+
+/// A column of floats stored as ids into a [`FloatDictionary`] of its distinct values.
+///
+/// ```rust
+/// use total_float_wrap::{EncodedColumn, TotalF64};
+///
+/// let prices: Vec<TotalF64> = [9.99, 4.5, 9.99, 9.99, 4.5].map(TotalF64).into();
+/// let column = EncodedColumn::encode(prices.iter().copied());
+///
+/// assert_eq!(column.dictionary.len(), 2);
+/// assert_eq!(column.ids, [1, 0, 1, 1, 0]);
+/// assert_eq!(column.decode(), prices);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EncodedColumn<K = TotalF64> {
+    pub dictionary: FloatDictionary<K>,
+    pub ids: Vec<u32>,
+}
+
+impl<K: Copy + Ord + Hash> EncodedColumn<K> {
+    /// Encodes the values, with ids in the total order of the values.
+    pub fn encode(column: impl IntoIterator<Item = K>) -> Self {
+        let mut interner = FloatInterner::new();
+        let ids: Vec<u32> = column.into_iter().map(|v| interner.intern(v)).collect();
+
+        let (dictionary, relabel) = interner.finish();
+        let ids = ids.into_iter().map(|id| relabel[id as usize]).collect();
+        Self { dictionary, ids }
+    }
+
+    /// Returns the `i`th value of the column
+    pub fn get(&self, i: usize) -> Option<K> {
+        self.dictionary.resolve(*self.ids.get(i)?)
+    }
+
+    /// Decodes the column back into its values.
+    ///
+    /// Panics if an id is not in the dictionary.
+    pub fn decode(&self) -> Vec<K> {
+        self.ids
+            .iter()
+            .map(|&id| self.dictionary.values[id as usize])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TotalF32;
+
+    #[test]
+    fn test_interner_distinguishes_by_total_order() {
+        let mut interner = FloatInterner::new();
+        let nan = f64::NAN;
+        let payload = f64::from_bits(nan.to_bits() | 7);
+
+        let ids = [0.0, -0.0, nan, payload, nan, 0.0].map(|v| interner.intern(TotalF64(v)));
+        assert_eq!(ids, [0, 1, 2, 3, 2, 0]);
+        assert_eq!(interner.len(), 4);
+        assert_eq!(interner.get(TotalF64(-0.0)), Some(1));
+        assert_eq!(interner.get(TotalF64(1.0)), None);
+        assert_eq!(
+            interner.resolve(3).map(|v| v.0.to_bits()),
+            Some(payload.to_bits())
+        );
+        assert_eq!(interner.resolve(4), None);
+    }
+
+    #[test]
+    fn test_interner_finish_orders_ids() {
+        let values = [3.0, -1.0, f64::NAN, -0.0, 0.0, 3.0, f64::NEG_INFINITY];
+        let mut interner = FloatInterner::new();
+        let ids: Vec<u32> = values
+            .iter()
+            .map(|&v| interner.intern(TotalF64(v)))
+            .collect();
+
+        let (dictionary, relabel) = interner.finish();
+        let ids: Vec<u32> = ids.iter().map(|&id| relabel[id as usize]).collect();
+        for (a, &x) in ids.iter().zip(&values) {
+            assert_eq!(dictionary.resolve(*a), Some(TotalF64(x)));
+            for (b, &y) in ids.iter().zip(&values) {
+                assert_eq!(a.cmp(b), TotalF64(x).cmp(&TotalF64(y)));
+            }
+        }
+        assert_eq!(dictionary.get(TotalF64(-0.0)), Some(2));
+        assert_eq!(dictionary.len(), 6);
+    }
+
+    #[test]
+    fn test_encoded_column() {
+        let column: Vec<TotalF32> = [1.5, 1.5, -2.0, f32::NAN, 1.5]
+            .into_iter()
+            .map(TotalF32)
+            .collect();
+        let encoded = EncodedColumn::encode(column.iter().copied());
+
+        assert_eq!(encoded.ids, [1, 1, 0, 2, 1]);
+        assert_eq!(encoded.decode(), column);
+        assert_eq!(encoded.get(2), Some(TotalF32(-2.0)));
+        assert_eq!(encoded.get(5), None);
+
+        let empty = EncodedColumn::<TotalF64>::encode([]);
+        assert!(empty.dictionary.is_empty());
+        assert!(empty.decode().is_empty());
+    }
+}
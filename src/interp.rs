@@ -0,0 +1,547 @@
+use core::fmt;
+
+use crate::TotalF64;
+
+/// How an [`InterpTable`] computes values between its breakpoints.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Interpolation {
+    /// Join neighbouring breakpoints with straight lines.
+    #[default]
+    Linear,
+    /// Hold the value of the breakpoint at or to the left of the input.
+    StepLeft,
+    /// Take the value of the breakpoint at or to the right of the input.
+    StepRight,
+    /// Take the value of the closest breakpoint, the left one when they are equally close.
+    Nearest,
+}
+
+/// How an [`InterpTable`] computes values beyond its first and last breakpoints.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Extrapolation {
+    /// Hold the value of the nearest end breakpoint.
+    #[default]
+    Clamp,
+    /// Extend the line through the two breakpoints at that end. The step and nearest
+    /// interpolations have no slope, so for them this clamps.
+    Linear,
+    /// Return [`InterpError::OutOfRange`].
+    Error,
+}
+
+/// The shape of the values of an [`InterpTable`], in increasing order of their keys.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Monotonicity {
+    /// Every value is greater than the one before it.
+    StrictlyIncreasing,
+    /// Every value is less than the one before it.
+    StrictlyDecreasing,
+    /// Every value is at least the one before it, and some are equal, as in a constant table or
+    /// one with a single breakpoint.
+    NonDecreasing,
+    /// Every value is at most the one before it, and some are equal.
+    NonIncreasing,
+    /// The values rise and fall, or some are NaN.
+    NotMonotonic,
+}
+
+/// Errors from building or looking up an [`InterpTable`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InterpError {
+    /// The table has no breakpoints.
+    Empty,
+    /// A breakpoint key is NaN or infinite.
+    NonFiniteKey,
+    /// The breakpoint keys are not strictly increasing.
+    Unsorted,
+    /// The input is NaN.
+    NanInput,
+    /// The input is beyond the table under [`Extrapolation::Error`].
+    OutOfRange,
+    /// An inverse lookup was made on a table which is not strictly monotonic.
+    NotMonotonic,
+    /// An inverse lookup was made on a table with a NaN or infinite value.
+    NonFiniteValue,
+    /// A breakpoint was pushed onto a full [`BoundedPoints`].
+    CapacityExceeded,
+}
+
+impl fmt::Display for InterpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InterpError::Empty => "interpolation table has no breakpoints",
+            InterpError::NonFiniteKey => "interpolation table has a non-finite key",
+            InterpError::Unsorted => "interpolation table keys are not strictly increasing",
+            InterpError::NanInput => "interpolation input is NaN",
+            InterpError::OutOfRange => "interpolation input is outside the table",
+            InterpError::NotMonotonic => "interpolation table is not strictly monotonic",
+            InterpError::NonFiniteValue => "interpolation table has a non-finite value",
+            InterpError::CapacityExceeded => "interpolation table is full",
+        })
+    }
+}
+
+impl core::error::Error for InterpError {}
+
+/// A table of `(key, value)` breakpoints for interpolating a function of one float.
+///
+/// The breakpoints are stored in `S`, which can be a `Vec` or slice, or without an allocator a
+/// fixed size array or a [`BoundedPoints`] for up to a fixed number of breakpoints. Keys must be
+/// finite and strictly increasing, so `-0.0` and `0.0` cannot both be keys.
+///
+/// ```rust
+/// use total_float_wrap::{Extrapolation, InterpTable, Interpolation, TotalF64};
+///
+/// let curve = [(0.0, 0.0), (10.0, 100.0), (20.0, 150.0)].map(|(x, y)| (TotalF64(x), y));
+/// let table = InterpTable::new(curve).unwrap();
+///
+/// assert_eq!(table.lookup(5.0), Ok(50.0));
+/// assert_eq!(table.lookup(30.0), Ok(150.0));
+/// assert_eq!(table.inverse(125.0), Ok(15.0));
+///
+/// let table = table
+///     .interpolation(Interpolation::StepLeft)
+///     .extrapolation(Extrapolation::Error);
+/// assert_eq!(table.lookup(15.0), Ok(100.0));
+/// assert!(table.lookup(-1.0).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterpTable<S> {
+    points: S,
+    interpolation: Interpolation,
+    extrapolation: Extrapolation,
+}
+
+impl<S: AsRef<[(TotalF64, f64)]>> InterpTable<S> {
+    /// Creates a table with linear interpolation and clamped extrapolation.
+    pub fn new(points: S) -> Result<Self, InterpError> {
+        let slice = points.as_ref();
+        if slice.is_empty() {
+            return Err(InterpError::Empty);
+        }
+        if slice.iter().any(|(k, _)| !k.0.is_finite()) {
+            return Err(InterpError::NonFiniteKey);
+        }
+        if slice.windows(2).any(|w| w[0].0 .0 >= w[1].0 .0) {
+            return Err(InterpError::Unsorted);
+        }
+
+        Ok(Self {
+            points,
+            interpolation: Interpolation::default(),
+            extrapolation: Extrapolation::default(),
+        })
+    }
+
+    /// Sets how values between breakpoints are computed.
+    pub fn interpolation(self, interpolation: Interpolation) -> Self {
+        Self {
+            interpolation,
+            ..self
+        }
+    }
+
+    /// Sets how values beyond the breakpoints are computed.
+    pub fn extrapolation(self, extrapolation: Extrapolation) -> Self {
+        Self {
+            extrapolation,
+            ..self
+        }
+    }
+
+    /// Returns the breakpoints, in increasing order of key
+    pub fn points(&self) -> &[(TotalF64, f64)] {
+        self.points.as_ref()
+    }
+
+    /// Returns the backing store of the breakpoints
+    pub fn into_inner(self) -> S {
+        self.points
+    }
+
+    /// Returns the shape of the values
+    pub fn monotonicity(&self) -> Monotonicity {
+        if self.points().len() < 2 {
+            return Monotonicity::NonDecreasing;
+        }
+        let values = || self.points().windows(2).map(|w| (w[0].1, w[1].1));
+        if values().all(|(a, b)| a < b) {
+            Monotonicity::StrictlyIncreasing
+        } else if values().all(|(a, b)| a > b) {
+            Monotonicity::StrictlyDecreasing
+        } else if values().all(|(a, b)| a <= b) {
+            Monotonicity::NonDecreasing
+        } else if values().all(|(a, b)| a >= b) {
+            Monotonicity::NonIncreasing
+        } else {
+            Monotonicity::NotMonotonic
+        }
+    }
+
+    /// Returns the value of the table at `x`.
+    pub fn lookup(&self, x: f64) -> Result<f64, InterpError> {
+        if x.is_nan() {
+            return Err(InterpError::NanInput);
+        }
+        let linear = self.interpolation == Interpolation::Linear;
+        interpolate(
+            self.points(),
+            |&(k, v)| (k.0, v),
+            x,
+            self.interpolation,
+            self.lookup_extrapolation(linear),
+        )
+    }
+
+    /// Returns the key at which the linearly interpolated table takes the value `y`.
+    ///
+    /// This inverts [`Interpolation::Linear`] whatever the table's interpolation is, and
+    /// applies the table's extrapolation to values beyond the ends. The values become the keys
+    /// of the inverse, so they must all be finite.
+    pub fn inverse(&self, y: f64) -> Result<f64, InterpError> {
+        if y.is_nan() {
+            return Err(InterpError::NanInput);
+        }
+
+        let points = self.points();
+        if points.iter().any(|(_, v)| !v.is_finite()) {
+            return Err(InterpError::NonFiniteValue);
+        }
+        match self.monotonicity() {
+            Monotonicity::StrictlyIncreasing => interpolate(
+                points,
+                |&(k, v)| (v, k.0),
+                y,
+                Interpolation::Linear,
+                self.extrapolation,
+            ),
+            Monotonicity::StrictlyDecreasing => {
+                // negating the values makes them increase without moving the keys they map to
+                interpolate(
+                    points,
+                    |&(k, v)| (-v, k.0),
+                    -y,
+                    Interpolation::Linear,
+                    self.extrapolation,
+                )
+            }
+            _ => Err(InterpError::NotMonotonic),
+        }
+    }
+
+    /// Returns the extrapolation, with linear extrapolation clamping for modes without a slope
+    fn lookup_extrapolation(&self, linear: bool) -> Extrapolation {
+        match self.extrapolation {
+            Extrapolation::Linear if !linear => Extrapolation::Clamp,
+            extrapolation => extrapolation,
+        }
+    }
+}
+
+/// Up to `N` breakpoints stored inline, as the backing store of an [`InterpTable`] which needs
+/// no allocator but whose length is only known at runtime.
+///
+/// ```rust
+/// use total_float_wrap::{BoundedPoints, InterpError, InterpTable, TotalF64};
+///
+/// let mut points = BoundedPoints::<4>::new();
+/// for x in [0.0, 1.0, 2.0] {
+///     points.push(TotalF64(x), x * x).unwrap();
+/// }
+/// let table = InterpTable::new(points).unwrap();
+/// assert_eq!(table.lookup(1.5), Ok(2.5));
+///
+/// let mut points = table.into_inner();
+/// points.push(TotalF64(3.0), 9.0).unwrap();
+/// assert_eq!(points.push(TotalF64(4.0), 16.0), Err(InterpError::CapacityExceeded));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct BoundedPoints<const N: usize> {
+    points: [(TotalF64, f64); N],
+    len: usize,
+}
+
+impl<const N: usize> BoundedPoints<N> {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self {
+            points: [(TotalF64(0.0), 0.0); N],
+            len: 0,
+        }
+    }
+
+    /// Appends a breakpoint, failing if all `N` are in use
+    pub fn push(&mut self, key: TotalF64, value: f64) -> Result<(), InterpError> {
+        let slot = self
+            .points
+            .get_mut(self.len)
+            .ok_or(InterpError::CapacityExceeded)?;
+        *slot = (key, value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes every breakpoint
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns the number of breakpoints
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no breakpoints
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the breakpoints
+    pub fn as_slice(&self) -> &[(TotalF64, f64)] {
+        &self.points[..self.len]
+    }
+}
+
+impl<const N: usize> Default for BoundedPoints<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> PartialEq for BoundedPoints<N> {
+    fn eq(&self, other: &Self) -> bool {
+        // slots past the length are stale
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<const N: usize> AsRef<[(TotalF64, f64)]> for BoundedPoints<N> {
+    fn as_ref(&self) -> &[(TotalF64, f64)] {
+        self.as_slice()
+    }
+}
+
+/// Interpolates between the `(x, y)` projections of `points` at the non-NaN `x`, where the
+/// projected `x` are finite and strictly increasing
+fn interpolate<P>(
+    points: &[P],
+    project: impl Fn(&P) -> (f64, f64),
+    x: f64,
+    interpolation: Interpolation,
+    extrapolation: Extrapolation,
+) -> Result<f64, InterpError> {
+    let len = points.len();
+    // the number of breakpoints at or before x
+    let after = points.partition_point(|p| project(p).0 <= x);
+
+    // the end breakpoint, and the segment there for linear extrapolation
+    let (outside, segment) = if after == 0 {
+        (0, 0..2)
+    } else if after == len && project(&points[len - 1]).0 < x {
+        (len - 1, len.saturating_sub(2)..len)
+    } else if after == len {
+        return Ok(project(&points[len - 1]).1);
+    } else {
+        let (left, right) = (project(&points[after - 1]), project(&points[after]));
+        return Ok(match interpolation {
+            Interpolation::Linear => line(left, right, x),
+            Interpolation::StepLeft => left.1,
+            Interpolation::StepRight if x == left.0 => left.1,
+            Interpolation::StepRight => right.1,
+            Interpolation::Nearest if x - left.0 <= right.0 - x => left.1,
+            Interpolation::Nearest => right.1,
+        });
+    };
+
+    match (extrapolation, points.get(segment)) {
+        (Extrapolation::Error, _) => Err(InterpError::OutOfRange),
+        (Extrapolation::Linear, Some([a, b])) => Ok(line(project(a), project(b), x)),
+        _ => Ok(project(&points[outside]).1),
+    }
+}
+
+/// Evaluates the line through `a` and `b` at `x`
+fn line(a: (f64, f64), b: (f64, f64), x: f64) -> f64 {
+    if x == a.0 {
+        return a.1;
+    }
+    let (rise, run, along) = (b.1 - a.1, b.0 - a.0, x - a.0);
+    if rise.is_finite() && run.is_finite() && along.is_finite() {
+        return a.1 + rise * (along / run);
+    }
+
+    // the differences of far apart points overflow, so take them between the halved points and
+    // add the halved rise twice
+    let (rise, run, along) = (
+        b.1 / 2.0 - a.1 / 2.0,
+        b.0 / 2.0 - a.0 / 2.0,
+        x / 2.0 - a.0 / 2.0,
+    );
+    let half = rise * (along / run);
+    a.1 + half + half
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(points: &[(f64, f64)]) -> InterpTable<Vec<(TotalF64, f64)>> {
+        InterpTable::new(points.iter().map(|&(k, v)| (TotalF64(k), v)).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_new_validates() {
+        let build = |points: &[(f64, f64)]| {
+            InterpTable::new(
+                points
+                    .iter()
+                    .map(|&(k, v)| (TotalF64(k), v))
+                    .collect::<Vec<_>>(),
+            )
+            .map(|_| ())
+        };
+        assert_eq!(build(&[]), Err(InterpError::Empty));
+        assert_eq!(build(&[(f64::NAN, 1.0)]), Err(InterpError::NonFiniteKey));
+        assert_eq!(
+            build(&[(1.0, 0.0), (f64::INFINITY, 1.0)]),
+            Err(InterpError::NonFiniteKey)
+        );
+        assert_eq!(
+            build(&[(-0.0, 0.0), (0.0, 1.0)]),
+            Err(InterpError::Unsorted)
+        );
+        assert_eq!(build(&[(1.0, 0.0), (0.5, 1.0)]), Err(InterpError::Unsorted));
+        assert_eq!(build(&[(1.0, f64::NAN)]), Ok(()));
+    }
+
+    #[test]
+    fn test_interpolation_modes() {
+        let t = table(&[(0.0, 0.0), (1.0, 10.0), (3.0, 30.0)]);
+        let at = |t: &InterpTable<_>, xs: [f64; 6]| xs.map(|x| t.lookup(x).unwrap());
+        let xs = [0.0, 0.5, 1.0, 2.0, 2.5, 3.0];
+
+        assert_eq!(at(&t, xs), [0.0, 5.0, 10.0, 20.0, 25.0, 30.0]);
+        let t = t.interpolation(Interpolation::StepLeft);
+        assert_eq!(at(&t, xs), [0.0, 0.0, 10.0, 10.0, 10.0, 30.0]);
+        let t = t.interpolation(Interpolation::StepRight);
+        assert_eq!(at(&t, xs), [0.0, 10.0, 10.0, 30.0, 30.0, 30.0]);
+        let t = t.interpolation(Interpolation::Nearest);
+        assert_eq!(at(&t, xs), [0.0, 0.0, 10.0, 10.0, 30.0, 30.0]);
+        assert_eq!(t.lookup(-0.0), Ok(0.0));
+        assert_eq!(t.lookup(f64::NAN), Err(InterpError::NanInput));
+
+        let squares: Vec<(f64, f64)> = (0..100).map(|i| (f64::from(i), f64::from(i * i))).collect();
+        let t = table(&squares);
+        assert_eq!(t.lookup(41.5), Ok(1722.5));
+        assert_eq!(t.inverse(1722.5), Ok(41.5));
+    }
+
+    #[test]
+    fn test_extrapolation() {
+        let t = table(&[(0.0, 0.0), (1.0, 10.0), (3.0, 20.0)]);
+        assert_eq!(t.lookup(-1.0), Ok(0.0));
+        assert_eq!(t.lookup(f64::INFINITY), Ok(20.0));
+
+        let t = t.extrapolation(Extrapolation::Linear);
+        assert_eq!(t.lookup(-1.0), Ok(-10.0));
+        assert_eq!(t.lookup(5.0), Ok(30.0));
+        let t = t.interpolation(Interpolation::StepRight);
+        assert_eq!(t.lookup(5.0), Ok(20.0));
+
+        let t = t.extrapolation(Extrapolation::Error);
+        assert_eq!(t.lookup(3.5), Err(InterpError::OutOfRange));
+        assert_eq!(t.lookup(3.0), Ok(20.0));
+
+        let single = table(&[(2.0, 7.0)]).extrapolation(Extrapolation::Linear);
+        assert_eq!(single.lookup(100.0), Ok(7.0));
+    }
+
+    #[test]
+    fn test_far_apart_points() {
+        let t = table(&[(-1e308, 0.0), (1e308, 2.0)]);
+        assert_eq!(t.lookup(0.0), Ok(1.0));
+        assert_eq!(t.inverse(1.0), Ok(0.0));
+
+        let max = f64::MAX;
+        let close = |found: Result<f64, _>, expected: f64| {
+            (found.unwrap() - expected).abs() <= expected.abs() * 4.0 * f64::EPSILON
+        };
+        let t = table(&[(-max, -max), (max, max)]);
+        assert_eq!(t.lookup(0.0), Ok(0.0));
+        assert!(close(t.lookup(max / 2.0), max / 2.0));
+        assert_eq!(t.lookup(max), Ok(max));
+        assert!(close(t.inverse(-max / 4.0), -max / 4.0));
+        let t = table(&[(-max, max), (0.0, 0.0), (max, -max)]);
+        assert_eq!(t.lookup(-max / 2.0), Ok(max / 2.0));
+        assert_eq!(t.inverse(max), Ok(-max));
+    }
+
+    #[test]
+    fn test_monotonicity_and_inverse() {
+        let rising = table(&[(0.0, 1.0), (1.0, 3.0), (2.0, 7.0)]);
+        assert_eq!(rising.monotonicity(), Monotonicity::StrictlyIncreasing);
+        assert_eq!(rising.inverse(5.0), Ok(1.5));
+        assert_eq!(rising.inverse(0.0), Ok(0.0));
+        let extended = rising.clone().extrapolation(Extrapolation::Linear);
+        assert_eq!(extended.inverse(11.0), Ok(3.0));
+
+        let falling = table(&[(0.0, 5.0), (2.0, 1.0), (4.0, 0.0)]);
+        assert_eq!(falling.monotonicity(), Monotonicity::StrictlyDecreasing);
+        assert_eq!(falling.inverse(3.0), Ok(1.0));
+        assert_eq!(falling.inverse(0.5), Ok(3.0));
+
+        let flat = table(&[(0.0, 1.0), (1.0, 1.0), (2.0, 2.0)]);
+        assert_eq!(flat.monotonicity(), Monotonicity::NonDecreasing);
+        assert_eq!(flat.inverse(1.5), Err(InterpError::NotMonotonic));
+        assert_eq!(
+            table(&[(0.0, 2.0), (1.0, 2.0), (2.0, 1.0)]).monotonicity(),
+            Monotonicity::NonIncreasing
+        );
+        assert_eq!(
+            table(&[(0.0, 1.0), (1.0, f64::NAN)]).monotonicity(),
+            Monotonicity::NotMonotonic
+        );
+        let unbounded = table(&[(0.0, f64::NEG_INFINITY), (1.0, 0.0), (2.0, f64::INFINITY)]);
+        assert_eq!(unbounded.monotonicity(), Monotonicity::StrictlyIncreasing);
+        assert_eq!(unbounded.inverse(-5.0), Err(InterpError::NonFiniteValue));
+
+        let single = table(&[(0.0, 1.0)]);
+        assert_eq!(single.monotonicity(), Monotonicity::NonDecreasing);
+        assert_eq!(single.inverse(1.0), Err(InterpError::NotMonotonic));
+    }
+
+    #[test]
+    fn test_fixed_capacity_store() {
+        const CURVE: [(TotalF64, f64); 3] = [
+            (TotalF64(-1.0), -2.0),
+            (TotalF64(0.0), 0.0),
+            (TotalF64(1.0), 2.0),
+        ];
+        let table = InterpTable::new(CURVE).unwrap();
+        assert_eq!(table.lookup(0.25), Ok(0.5));
+
+        let borrowed = InterpTable::new(&CURVE[1..]).unwrap();
+        assert_eq!(borrowed.points().len(), 2);
+        assert_eq!(table.into_inner(), CURVE);
+
+        let mut bounded = BoundedPoints::<3>::default();
+        assert_eq!(
+            InterpTable::new(bounded).map(|_| ()),
+            Err(InterpError::Empty)
+        );
+        for &(k, v) in CURVE.iter().rev() {
+            bounded.push(k, v).unwrap();
+        }
+        assert_eq!(
+            InterpTable::new(bounded).map(|_| ()),
+            Err(InterpError::Unsorted)
+        );
+        bounded.clear();
+        for &(k, v) in &CURVE[..2] {
+            bounded.push(k, v).unwrap();
+        }
+        let table = InterpTable::new(bounded).unwrap();
+        assert_eq!(table.lookup(-0.5), Ok(-1.0));
+        assert_eq!(table.points(), &CURVE[..2]);
+    }
+}
@@ -14,9 +14,126 @@
 //!
 //! assert_eq!(map.get(&1.0.into()), Some(&10));
 //! ```
+//!
+//! The wrappers, and the modules which need neither allocation nor `std`, are available with
+//! `default-features = false`. Everything else needs the default `std` feature.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "std")]
+mod cluster;
+#[cfg(feature = "std")]
+pub use cluster::{
+    cluster_by_tolerance, cluster_by_tolerance_by_key, cluster_by_ulps, cluster_by_ulps_by_key,
+    Cluster,
+};
+
+#[cfg(feature = "std")]
+mod compressed_set;
+#[cfg(feature = "std")]
+pub use compressed_set::{CompressedF32Set, CompressedF64Set, DecodeError};
+
+#[cfg(feature = "std")]
+mod event_queue;
+#[cfg(feature = "std")]
+pub use event_queue::{EventHandle, EventQueue, NanPolicy, NanTimeError};
+
+#[cfg(feature = "std")]
+mod float_hasher;
+#[cfg(feature = "std")]
+pub use float_hasher::{FloatBuildHasher, FloatHasher, FloatMap, FloatSet};
+
+#[cfg(feature = "std")]
+mod float_index;
+#[cfg(feature = "std")]
+pub use float_index::{
+    IndexError, IndexKey, IndexReader, IndexWriter, KeyKind, ValueLayout, INDEX_MAGIC,
+    INDEX_VERSION,
+};
+
+#[cfg(feature = "std")]
+mod gorilla;
+#[cfg(feature = "std")]
+pub use gorilla::{GorillaDecoder, GorillaEncoder, GorillaFloat, GorillaSeries, DEFAULT_BLOCK_LEN};
+
+#[cfg(feature = "std")]
+mod grouping;
+#[cfg(feature = "std")]
+pub use grouping::{EqualityPolicy, FloatCounter, FloatGroups};
+
+mod integer;
+pub use integer::{IntConversionError, Integer};
+
+#[cfg(feature = "std")]
+mod interner;
+#[cfg(feature = "std")]
+pub use interner::{EncodedColumn, FloatDictionary, FloatInterner};
+
+mod interp;
+pub use interp::{
+    BoundedPoints, Extrapolation, InterpError, InterpTable, Interpolation, Monotonicity,
+};
+
+#[cfg(feature = "std")]
+mod lookup;
+#[cfg(feature = "std")]
+pub use lookup::{BTreeMapF64Ext, MapF64Ext};
+
+#[cfg(feature = "std")]
+mod nearest;
+#[cfg(feature = "std")]
+pub use nearest::{NearestExt, TieBreak};
+
+#[cfg(feature = "std")]
+mod numeric_range;
+#[cfg(feature = "std")]
+pub use numeric_range::{numeric_range, NumericRange, NumericRangeExt};
+
+mod ordered_key;
+pub use ordered_key::{OrderedKey32, OrderedKey64};
+
+mod py_hash;
+pub use py_hash::{py_hash_f64, py_hash_int, py_hash_uint, PyHash, PyHashed};
+
+#[cfg(feature = "std")]
+mod quantize;
+#[cfg(feature = "std")]
+pub use quantize::{weld_points, CellsWithin, Quantized, Quantizer, QuantizerError};
+
+mod rounding;
+pub use rounding::{InexactError, RoundingMode};
+
+#[cfg(feature = "std")]
+mod sorted_total_vec;
+#[cfg(feature = "std")]
+pub use sorted_total_vec::SortedTotalVec;
+
+#[cfg(feature = "std")]
+mod spatial_grid;
+#[cfg(feature = "std")]
+pub use spatial_grid::{GridHandle, Neighbour, SpatialHashGrid};
+
+#[cfg(feature = "std")]
+mod stable_hash;
+#[cfg(feature = "std")]
+pub use stable_hash::{StableHash, StableHasher, STABLE_HASH_VERSION};
+
+#[cfg(feature = "std")]
+mod static_float;
+#[cfg(feature = "std")]
+pub use static_float::{StaticFloatMap, StaticFloatSet};
+
+mod total_array;
+pub use total_array::{TotalArray, TotalPoint, TotalPoint2, TotalPoint3};
 
 mod total_f32;
 pub use total_f32::TotalF32;
 
 mod total_f64;
 pub use total_f64::TotalF64;
+
+mod total_float;
+pub use total_float::TotalFloat;
+
+mod total_number;
+pub use total_number::TotalNumber;
@@ -0,0 +1,156 @@
+use core::hash::BuildHasher;
+use core::ops::{Bound, RangeBounds};
+use std::collections::{btree_map, BTreeMap, HashMap};
+
+use crate::TotalF64;
+
+/// Lookups into maps keyed by `TotalF64` using a raw `f64`.
+///
+/// `f64` implements neither `Hash` nor `Eq`, so it cannot be used to query a hash map directly,
+/// through `Borrow` or through hashbrown / indexmap's `Equivalent`, which both also require the
+/// query to implement `Hash`.
+/// These methods wrap the `f64` instead, so lookups behave exactly like those with a `TotalF64`.
+///
+/// Implemented for `HashMap` and `BTreeMap`, and behind the `hashbrown` and `indexmap` features
+/// for `hashbrown::HashMap` and `indexmap::IndexMap`.
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use total_float_wrap::{MapF64Ext, TotalF64};
+///
+/// let mut map: HashMap<TotalF64, u64> = HashMap::new();
+/// map.insert(1.0.into(), 10);
+///
+/// assert_eq!(map.get_f64(1.0), Some(&10));
+/// assert!(!map.contains_f64(-1.0));
+/// ```
+pub trait MapF64Ext<V> {
+    /// Returns a reference to the value stored under `TotalF64(key)`.
+    fn get_f64(&self, key: f64) -> Option<&V>;
+
+    /// Returns a mutable reference to the value stored under `TotalF64(key)`.
+    fn get_f64_mut(&mut self, key: f64) -> Option<&mut V>;
+
+    /// Returns whether the map contains `TotalF64(key)`.
+    fn contains_f64(&self, key: f64) -> bool {
+        self.get_f64(key).is_some()
+    }
+}
+
+impl<V, S: BuildHasher> MapF64Ext<V> for HashMap<TotalF64, V, S> {
+    fn get_f64(&self, key: f64) -> Option<&V> {
+        self.get(&TotalF64(key))
+    }
+
+    fn get_f64_mut(&mut self, key: f64) -> Option<&mut V> {
+        self.get_mut(&TotalF64(key))
+    }
+}
+
+impl<V> MapF64Ext<V> for BTreeMap<TotalF64, V> {
+    fn get_f64(&self, key: f64) -> Option<&V> {
+        self.get(&TotalF64(key))
+    }
+
+    fn get_f64_mut(&mut self, key: f64) -> Option<&mut V> {
+        self.get_mut(&TotalF64(key))
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<V, S: BuildHasher> MapF64Ext<V> for hashbrown::HashMap<TotalF64, V, S> {
+    fn get_f64(&self, key: f64) -> Option<&V> {
+        self.get(&TotalF64(key))
+    }
+
+    fn get_f64_mut(&mut self, key: f64) -> Option<&mut V> {
+        self.get_mut(&TotalF64(key))
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<V, S: BuildHasher> MapF64Ext<V> for indexmap::IndexMap<TotalF64, V, S> {
+    fn get_f64(&self, key: f64) -> Option<&V> {
+        self.get(&TotalF64(key))
+    }
+
+    fn get_f64_mut(&mut self, key: f64) -> Option<&mut V> {
+        self.get_mut(&TotalF64(key))
+    }
+}
+
+/// Range queries into a `BTreeMap` keyed by `TotalF64` using raw `f64` bounds.
+pub trait BTreeMapF64Ext<V> {
+    /// Iterates over the entries whose keys lie within `range`, under the total order.
+    ///
+    /// This is `BTreeMap::range` with each bound wrapped in a `TotalF64`, so `-0.0` sorts before
+    /// `0.0` and NaNs sort at the ends. It panics in the same situations.
+    fn range_f64<R: RangeBounds<f64>>(&self, range: R) -> btree_map::Range<'_, TotalF64, V>;
+}
+
+impl<V> BTreeMapF64Ext<V> for BTreeMap<TotalF64, V> {
+    fn range_f64<R: RangeBounds<f64>>(&self, range: R) -> btree_map::Range<'_, TotalF64, V> {
+        let start: Bound<TotalF64> = range.start_bound().cloned().map(TotalF64);
+        let end: Bound<TotalF64> = range.end_bound().cloned().map(TotalF64);
+        self.range((start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_f64_ext() {
+        let mut hash: HashMap<TotalF64, u32> = HashMap::new();
+        let mut btree: BTreeMap<TotalF64, u32> = BTreeMap::new();
+        for (k, v) in [(1.0, 10), (-0.0, 20), (f64::NAN, 30)] {
+            hash.insert(TotalF64(k), v);
+            btree.insert(TotalF64(k), v);
+        }
+
+        for map in [&mut hash as &mut dyn MapF64Ext<u32>, &mut btree] {
+            assert_eq!(map.get_f64(1.0), Some(&10));
+            assert_eq!(map.get_f64(-0.0), Some(&20));
+            assert_eq!(map.get_f64(0.0), None);
+            assert_eq!(map.get_f64(f64::NAN), Some(&30));
+
+            *map.get_f64_mut(1.0).unwrap() += 1;
+            assert_eq!(map.get_f64(1.0), Some(&11));
+            assert!(map.contains_f64(-0.0));
+            assert!(!map.contains_f64(2.0));
+        }
+    }
+
+    #[test]
+    fn test_range_f64() {
+        let map: BTreeMap<TotalF64, u32> = [(-1.0, 0), (-0.0, 1), (0.0, 2), (0.5, 3), (1.0, 4)]
+            .into_iter()
+            .map(|(k, v)| (TotalF64(k), v))
+            .collect();
+
+        let values: Vec<u32> = map.range_f64(-0.0..1.0).map(|(_, v)| *v).collect();
+        assert_eq!(values, [1, 2, 3]);
+
+        let values: Vec<u32> = map.range_f64(..=0.0).map(|(_, v)| *v).collect();
+        assert_eq!(values, [0, 1, 2]);
+    }
+
+    #[cfg(feature = "hashbrown")]
+    #[test]
+    fn test_map_f64_ext_hashbrown() {
+        let mut map: hashbrown::HashMap<TotalF64, u32> = hashbrown::HashMap::new();
+        map.insert(TotalF64(2.5), 1);
+        assert_eq!(map.get_f64(2.5), Some(&1));
+        assert_eq!(map.get_f64(-2.5), None);
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_map_f64_ext_indexmap() {
+        let mut map: indexmap::IndexMap<TotalF64, u32> = indexmap::IndexMap::new();
+        map.insert(TotalF64(2.5), 1);
+        assert_eq!(map.get_f64(2.5), Some(&1));
+        assert_eq!(map.get_f64(-2.5), None);
+    }
+}
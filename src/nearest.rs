@@ -0,0 +1,382 @@
+use core::ops::Bound;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::numeric_range::slice_indices;
+use crate::{IndexReader, NumericRange, NumericRangeExt, TotalF64};
+
+/// Which key wins when two keys are the same distance from the query.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum TieBreak {
+    /// Prefer the key below the query.
+    #[default]
+    Lower,
+    /// Prefer the key above the query.
+    Higher,
+}
+
+/// Nearest key lookups into sorted collections of `TotalF64` keys.
+///
+/// Implemented for `BTreeMap` and `BTreeSet`, whose entries are `(&key, &value)` and `&key`,
+/// for sorted slices, whose entries are `(index, &key)`, and for [`IndexReader`], whose entries
+/// are `(key, value)`.
+///
+/// Keys are compared numerically, as with [`NumericRange`]:
+///
+/// - both zeros are equal to the query `0.0`, whatever their signs,
+/// - NaN keys are never returned, and a NaN query finds nothing,
+/// - the distance between a key and the query is `|key - query|` computed in `f64`, or zero when
+///   they are numerically equal. Infinite keys are infinitely far from every finite query, and
+///   every other key is infinitely far from an infinite query.
+///   Distances which overflow become infinite too, so such keys tie with each other.
+pub trait NearestExt {
+    type Entry<'a>
+    where
+        Self: 'a;
+
+    /// Returns the entry with the greatest key numerically less than or equal to `query`.
+    fn floor_entry(&self, query: f64) -> Option<Self::Entry<'_>>;
+
+    /// Returns the entry with the least key numerically greater than or equal to `query`.
+    fn ceiling_entry(&self, query: f64) -> Option<Self::Entry<'_>>;
+
+    /// Returns the entry whose key is closest to `query`, using `tie` to pick between keys the
+    /// same distance either side of it.
+    fn nearest_entry(&self, query: f64, tie: TieBreak) -> Option<Self::Entry<'_>> {
+        self.nearest_k(query, 1, tie).pop()
+    }
+
+    /// Returns up to `k` entries ordered by increasing distance from `query`, using `tie` to order
+    /// keys the same distance either side of it.
+    fn nearest_k(&self, query: f64, k: usize, tie: TieBreak) -> Vec<Self::Entry<'_>>;
+}
+
+/// The keys numerically at or below the query
+fn at_or_below(query: f64) -> NumericRange {
+    NumericRange::new(..=query)
+}
+
+/// The keys numerically at or above the query
+fn at_or_above(query: f64) -> NumericRange {
+    NumericRange::new(query..)
+}
+
+/// The keys numerically above the query
+fn above(query: f64) -> NumericRange {
+    NumericRange::new((Bound::Excluded(query), Bound::Unbounded))
+}
+
+/// The distance from the query, zero for numerically equal keys so that infinities are handled
+fn distance(query: f64, key: f64) -> f64 {
+    if key == query {
+        0.0
+    } else {
+        (key - query).abs()
+    }
+}
+
+/// Merges the keys below the query, nearest first, with those above it, nearest first
+fn nearest_k<E>(
+    query: f64,
+    k: usize,
+    tie: TieBreak,
+    below: impl Iterator<Item = E>,
+    above: impl Iterator<Item = E>,
+    key: impl Fn(&E) -> f64,
+) -> Vec<E> {
+    let mut below = below.peekable();
+    let mut above = above.peekable();
+    let mut nearest = Vec::with_capacity(k.min(16));
+
+    while nearest.len() < k {
+        let take_below = match (below.peek(), above.peek()) {
+            (None, None) => break,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some(b), Some(a)) => {
+                let (db, da) = (distance(query, key(b)), distance(query, key(a)));
+                db < da || (db == da && tie == TieBreak::Lower)
+            }
+        };
+
+        let next = if take_below {
+            below.next()
+        } else {
+            above.next()
+        };
+        nearest.extend(next);
+    }
+
+    nearest
+}
+
+impl<V> NearestExt for BTreeMap<TotalF64, V> {
+    type Entry<'a>
+        = (&'a TotalF64, &'a V)
+    where
+        V: 'a;
+
+    fn floor_entry(&self, query: f64) -> Option<Self::Entry<'_>> {
+        self.numeric_range(at_or_below(query)).next_back()
+    }
+
+    fn ceiling_entry(&self, query: f64) -> Option<Self::Entry<'_>> {
+        self.numeric_range(at_or_above(query)).next()
+    }
+
+    fn nearest_k(&self, query: f64, k: usize, tie: TieBreak) -> Vec<Self::Entry<'_>> {
+        nearest_k(
+            query,
+            k,
+            tie,
+            self.numeric_range(at_or_below(query)).rev(),
+            self.numeric_range(above(query)),
+            |(key, _)| key.0,
+        )
+    }
+}
+
+impl NearestExt for BTreeSet<TotalF64> {
+    type Entry<'a> = &'a TotalF64;
+
+    fn floor_entry(&self, query: f64) -> Option<Self::Entry<'_>> {
+        self.numeric_range(at_or_below(query)).next_back()
+    }
+
+    fn ceiling_entry(&self, query: f64) -> Option<Self::Entry<'_>> {
+        self.numeric_range(at_or_above(query)).next()
+    }
+
+    fn nearest_k(&self, query: f64, k: usize, tie: TieBreak) -> Vec<Self::Entry<'_>> {
+        nearest_k(
+            query,
+            k,
+            tie,
+            self.numeric_range(at_or_below(query)).rev(),
+            self.numeric_range(above(query)),
+            |key| key.0,
+        )
+    }
+}
+
+impl NearestExt for [TotalF64] {
+    type Entry<'a> = (usize, &'a TotalF64);
+
+    /// The slice must be sorted by the total order.
+    fn floor_entry(&self, query: f64) -> Option<Self::Entry<'_>> {
+        let i = slice_indices(self, &at_or_below(query)).next_back()?;
+        Some((i, &self[i]))
+    }
+
+    /// The slice must be sorted by the total order.
+    fn ceiling_entry(&self, query: f64) -> Option<Self::Entry<'_>> {
+        let i = slice_indices(self, &at_or_above(query)).next()?;
+        Some((i, &self[i]))
+    }
+
+    /// The slice must be sorted by the total order.
+    fn nearest_k(&self, query: f64, k: usize, tie: TieBreak) -> Vec<Self::Entry<'_>> {
+        nearest_k(
+            query,
+            k,
+            tie,
+            slice_indices(self, &at_or_below(query))
+                .rev()
+                .map(|i| (i, &self[i])),
+            slice_indices(self, &above(query)).map(|i| (i, &self[i])),
+            |(_, key)| key.0,
+        )
+    }
+}
+
+impl<'a> NearestExt for IndexReader<'a> {
+    type Entry<'b>
+        = (TotalF64, &'a [u8])
+    where
+        'a: 'b;
+
+    fn floor_entry(&self, query: f64) -> Option<Self::Entry<'_>> {
+        self.range(at_or_below(query)).next_back()
+    }
+
+    fn ceiling_entry(&self, query: f64) -> Option<Self::Entry<'_>> {
+        self.range(at_or_above(query)).next()
+    }
+
+    fn nearest_k(&self, query: f64, k: usize, tie: TieBreak) -> Vec<Self::Entry<'_>> {
+        nearest_k(
+            query,
+            k,
+            tie,
+            self.range(at_or_below(query)).rev(),
+            self.range(above(query)),
+            |(key, _)| key.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(values: &[f64]) -> Vec<TotalF64> {
+        let mut keys: Vec<TotalF64> = values.iter().copied().map(TotalF64).collect();
+        keys.sort();
+        keys
+    }
+
+    /// Runs the lookups against every supported collection, checking they agree
+    fn check(values: &[f64], query: f64, expected: [Option<f64>; 4], tie: TieBreak) {
+        let keys = keys(values);
+        let set: BTreeSet<TotalF64> = keys.iter().copied().collect();
+        let map: BTreeMap<TotalF64, usize> = keys.iter().copied().zip(0..).collect();
+
+        let from_set = [
+            set.floor_entry(query).copied(),
+            set.ceiling_entry(query).copied(),
+            set.nearest_entry(query, TieBreak::Lower).copied(),
+            set.nearest_entry(query, TieBreak::Higher).copied(),
+        ];
+        let from_map = [
+            map.floor_entry(query).map(|(k, _)| *k),
+            map.ceiling_entry(query).map(|(k, _)| *k),
+            map.nearest_entry(query, TieBreak::Lower).map(|(k, _)| *k),
+            map.nearest_entry(query, TieBreak::Higher).map(|(k, _)| *k),
+        ];
+        let from_slice = [
+            keys.floor_entry(query).map(|(_, k)| *k),
+            keys.ceiling_entry(query).map(|(_, k)| *k),
+            keys.nearest_entry(query, TieBreak::Lower).map(|(_, k)| *k),
+            keys.nearest_entry(query, TieBreak::Higher).map(|(_, k)| *k),
+        ];
+
+        let expected = expected.map(|e| e.map(TotalF64));
+        assert_eq!(from_set, expected, "{tie:?} {query}");
+        assert_eq!(from_map, expected, "{tie:?} {query}");
+        assert_eq!(from_slice, expected, "{tie:?} {query}");
+
+        for (i, k) in keys.nearest_k(query, keys.len(), tie) {
+            assert_eq!(map[k], i);
+        }
+    }
+
+    #[test]
+    fn test_nearest_entry() {
+        let values = [-2.0, 1.0, 3.0, 4.0];
+        let lower = TieBreak::Lower;
+        check(
+            &values,
+            2.0,
+            [Some(1.0), Some(3.0), Some(1.0), Some(3.0)],
+            lower,
+        );
+        check(
+            &values,
+            2.9,
+            [Some(1.0), Some(3.0), Some(3.0), Some(3.0)],
+            lower,
+        );
+        check(
+            &values,
+            3.0,
+            [Some(3.0), Some(3.0), Some(3.0), Some(3.0)],
+            lower,
+        );
+        check(
+            &values,
+            -5.0,
+            [None, Some(-2.0), Some(-2.0), Some(-2.0)],
+            lower,
+        );
+        check(&values, 5.0, [Some(4.0), None, Some(4.0), Some(4.0)], lower);
+        check(&[], 5.0, [None; 4], lower);
+    }
+
+    #[test]
+    fn test_nearest_entry_zeros_and_nans() {
+        let values = [-f64::NAN, -1.0, -0.0, 0.0, f64::NAN];
+        check(
+            &values,
+            0.0,
+            [Some(0.0), Some(-0.0), Some(0.0), Some(0.0)],
+            TieBreak::Lower,
+        );
+        check(
+            &values,
+            -0.0,
+            [Some(0.0), Some(-0.0), Some(0.0), Some(0.0)],
+            TieBreak::Higher,
+        );
+        check(
+            &values,
+            7.0,
+            [Some(0.0), None, Some(0.0), Some(0.0)],
+            TieBreak::Lower,
+        );
+        check(&values, f64::NAN, [None; 4], TieBreak::Lower);
+        check(&[f64::NAN], 1.0, [None; 4], TieBreak::Lower);
+    }
+
+    #[test]
+    fn test_nearest_entry_infinities() {
+        let values = [f64::NEG_INFINITY, 0.0, f64::INFINITY];
+        check(
+            &values,
+            1e308,
+            [Some(0.0), Some(f64::INFINITY), Some(0.0), Some(0.0)],
+            TieBreak::Lower,
+        );
+        check(
+            &values,
+            f64::INFINITY,
+            [
+                Some(f64::INFINITY),
+                Some(f64::INFINITY),
+                Some(f64::INFINITY),
+                Some(f64::INFINITY),
+            ],
+            TieBreak::Lower,
+        );
+
+        let infinite_only = [f64::NEG_INFINITY, f64::INFINITY];
+        check(
+            &infinite_only,
+            0.0,
+            [
+                Some(f64::NEG_INFINITY),
+                Some(f64::INFINITY),
+                Some(f64::NEG_INFINITY),
+                Some(f64::INFINITY),
+            ],
+            TieBreak::Lower,
+        );
+    }
+
+    #[test]
+    fn test_nearest_k() {
+        let keys = keys(&[1.0, 2.0, 3.0, 5.0, 8.0, f64::NAN]);
+        let set: BTreeSet<TotalF64> = keys.iter().copied().collect();
+
+        let nearest: Vec<f64> = set
+            .nearest_k(3.0, 4, TieBreak::Lower)
+            .into_iter()
+            .map(|k| k.0)
+            .collect();
+        assert_eq!(nearest, [3.0, 2.0, 1.0, 5.0]);
+
+        let nearest: Vec<f64> = set
+            .nearest_k(3.5, 4, TieBreak::Higher)
+            .into_iter()
+            .map(|k| k.0)
+            .collect();
+        assert_eq!(nearest, [3.0, 5.0, 2.0, 1.0]);
+
+        let nearest: Vec<usize> = keys
+            .nearest_k(4.0, 10, TieBreak::Higher)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(nearest, [3, 2, 1, 0, 4]);
+
+        assert!(set.nearest_k(4.0, 0, TieBreak::Lower).is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+use core::ops::{Bound, RangeBounds};
+use std::collections::{btree_map, btree_set, BTreeMap, BTreeSet};
+
+use crate::TotalF64;
+
+/// An interval of real numbers, expressed as bounds in the total order.
+///
+/// Ranging over `TotalF64` keys directly follows the total order, so `TotalF64(0.0)..` skips
+/// `-0.0` but includes every positive NaN. A `NumericRange` instead selects the keys a person
+/// asking for "values between a and b" expects:
+///
+/// - both zeros are included whenever 0 lies in the interval, whatever the sign of the bounds,
+/// - NaNs are excluded, unless [`include_nans`](NumericRange::include_nans) is used, in which
+///   case an unbounded end extends over the NaNs of the same sign,
+/// - a NaN bound, or a lower bound above the upper bound, gives an empty range.
+///
+/// It implements `RangeBounds<TotalF64>`, and never makes `BTreeMap::range` panic.
+///
+/// ```rust
+/// use std::collections::BTreeSet;
+/// use total_float_wrap::{numeric_range, TotalF64};
+///
+/// let set: BTreeSet<TotalF64> = [-1.0, -0.0, 0.0, 1.0, f64::NAN].map(TotalF64).into();
+/// let keys: Vec<f64> = set.range(numeric_range(0.0..)).map(|k| k.0).collect();
+///
+/// assert_eq!(format!("{keys:?}"), "[-0.0, 0.0, 1.0]");
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NumericRange {
+    start: Bound<TotalF64>,
+    end: Bound<TotalF64>,
+    bounds: (Bound<TotalF64>, Bound<TotalF64>),
+}
+
+/// Builds the [`NumericRange`] covering the real numbers in `range`.
+pub fn numeric_range<R: RangeBounds<f64>>(range: R) -> NumericRange {
+    NumericRange::new(range)
+}
+
+/// A range which contains no values and which `BTreeMap::range` accepts
+const EMPTY: (Bound<TotalF64>, Bound<TotalF64>) = (
+    Bound::Excluded(TotalF64(0.0)),
+    Bound::Included(TotalF64(0.0)),
+);
+
+impl NumericRange {
+    /// Builds the range covering the real numbers in `range`, excluding NaNs.
+    pub fn new<R: RangeBounds<f64>>(range: R) -> Self {
+        let start = range.start_bound().cloned().map(TotalF64);
+        let end = range.end_bound().cloned().map(TotalF64);
+        Self {
+            start,
+            end,
+            bounds: Self::total_bounds(start, end, false),
+        }
+    }
+
+    /// Extends the unbounded ends of the range over the NaNs of the same sign.
+    ///
+    /// This only affects unbounded ends, NaNs lie outside of every explicit bound.
+    pub fn include_nans(self) -> Self {
+        Self {
+            bounds: Self::total_bounds(self.start, self.end, true),
+            ..self
+        }
+    }
+
+    /// Returns whether the range contains no values at all.
+    pub fn is_empty(&self) -> bool {
+        self.bounds == EMPTY
+    }
+
+    /// Converts the numeric bounds to inclusive total order bounds, working in the normalised
+    /// integers so that excluded bounds become the adjacent value in the total order
+    fn total_bounds(
+        start: Bound<TotalF64>,
+        end: Bound<TotalF64>,
+        nans: bool,
+    ) -> (Bound<TotalF64>, Bound<TotalF64>) {
+        let lo = match start {
+            Bound::Unbounded if nans => Some(i64::MIN),
+            Bound::Unbounded => Some(TotalF64(f64::NEG_INFINITY).normalise()),
+            Bound::Included(a) | Bound::Excluded(a) if a.0.is_nan() => None,
+            Bound::Included(a) if a.0 == 0.0 => Some(TotalF64(-0.0).normalise()),
+            Bound::Included(a) => Some(a.normalise()),
+            Bound::Excluded(a) if a.0 == 0.0 => TotalF64(0.0).normalise().checked_add(1),
+            Bound::Excluded(a) => a.normalise().checked_add(1),
+        };
+
+        let hi = match end {
+            Bound::Unbounded if nans => Some(i64::MAX),
+            Bound::Unbounded => Some(TotalF64(f64::INFINITY).normalise()),
+            Bound::Included(b) | Bound::Excluded(b) if b.0.is_nan() => None,
+            Bound::Included(b) if b.0 == 0.0 => Some(TotalF64(0.0).normalise()),
+            Bound::Included(b) => Some(b.normalise()),
+            Bound::Excluded(b) if b.0 == 0.0 => TotalF64(-0.0).normalise().checked_sub(1),
+            Bound::Excluded(b) => b.normalise().checked_sub(1),
+        };
+
+        match (lo, hi) {
+            (Some(lo), Some(hi)) if lo <= hi => (
+                Bound::Included(TotalF64::from_normalised(lo)),
+                Bound::Included(TotalF64::from_normalised(hi)),
+            ),
+            _ => EMPTY,
+        }
+    }
+}
+
+impl<R: RangeBounds<f64>> From<R> for NumericRange {
+    fn from(range: R) -> Self {
+        Self::new(range)
+    }
+}
+
+impl RangeBounds<TotalF64> for NumericRange {
+    fn start_bound(&self) -> Bound<&TotalF64> {
+        self.bounds.0.as_ref()
+    }
+
+    fn end_bound(&self) -> Bound<&TotalF64> {
+        self.bounds.1.as_ref()
+    }
+}
+
+/// Numeric range queries over sorted collections of `TotalF64`, see [`NumericRange`].
+///
+/// Implemented for `BTreeMap`, `BTreeSet` and sorted slices.
+pub trait NumericRangeExt {
+    type Range<'a>
+    where
+        Self: 'a;
+
+    /// Returns the part of the collection whose keys lie within `range`.
+    fn numeric_range<R: Into<NumericRange>>(&self, range: R) -> Self::Range<'_>;
+}
+
+impl<V> NumericRangeExt for BTreeMap<TotalF64, V> {
+    type Range<'a>
+        = btree_map::Range<'a, TotalF64, V>
+    where
+        V: 'a;
+
+    fn numeric_range<R: Into<NumericRange>>(&self, range: R) -> Self::Range<'_> {
+        self.range(range.into())
+    }
+}
+
+impl NumericRangeExt for BTreeSet<TotalF64> {
+    type Range<'a> = btree_set::Range<'a, TotalF64>;
+
+    fn numeric_range<R: Into<NumericRange>>(&self, range: R) -> Self::Range<'_> {
+        self.range(range.into())
+    }
+}
+
+impl NumericRangeExt for [TotalF64] {
+    type Range<'a> = &'a [TotalF64];
+
+    /// The slice must be sorted by the total order.
+    fn numeric_range<R: Into<NumericRange>>(&self, range: R) -> Self::Range<'_> {
+        &self[slice_indices(self, &range.into())]
+    }
+}
+
+/// Finds the indices of the part of a sorted slice within `range`
+pub(crate) fn slice_indices(slice: &[TotalF64], range: &NumericRange) -> core::ops::Range<usize> {
+    if range.is_empty() {
+        return 0..0;
+    }
+
+    let start = slice.partition_point(|x| match range.start_bound() {
+        Bound::Included(s) => x < s,
+        Bound::Excluded(s) => x <= s,
+        Bound::Unbounded => false,
+    });
+    let end = slice.partition_point(|x| match range.end_bound() {
+        Bound::Included(e) => x <= e,
+        Bound::Excluded(e) => x < e,
+        Bound::Unbounded => true,
+    });
+
+    start..end.max(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> Vec<TotalF64> {
+        let mut keys: Vec<TotalF64> = [
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            -1.0,
+            -0.0,
+            0.0,
+            0.5,
+            1.0,
+            f64::INFINITY,
+            f64::NAN,
+        ]
+        .map(TotalF64)
+        .into();
+        keys.sort();
+        keys
+    }
+
+    /// Runs the query against every supported collection, checking they agree
+    fn query(range: impl Into<NumericRange>) -> Vec<f64> {
+        let range = range.into();
+        let keys = keys();
+        let set: BTreeSet<TotalF64> = keys.iter().copied().collect();
+        let map: BTreeMap<TotalF64, ()> = keys.iter().map(|&k| (k, ())).collect();
+
+        let from_slice: Vec<TotalF64> = keys.numeric_range(range).to_vec();
+        let from_set: Vec<TotalF64> = set.numeric_range(range).copied().collect();
+        let from_map: Vec<TotalF64> = map.numeric_range(range).map(|(k, _)| *k).collect();
+        assert_eq!(from_slice, from_set);
+        assert_eq!(from_slice, from_map);
+
+        from_slice.into_iter().map(|k| k.0).collect()
+    }
+
+    /// Compares by bits so that the signs of zeros and NaNs are checked
+    fn assert_keys(actual: Vec<f64>, expected: &[f64]) {
+        let actual: Vec<TotalF64> = actual.into_iter().map(TotalF64).collect();
+        let expected: Vec<TotalF64> = expected.iter().copied().map(TotalF64).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_numeric_range_zeros() {
+        assert_keys(query(0.0..), &[-0.0, 0.0, 0.5, 1.0, f64::INFINITY]);
+        assert_keys(query(-0.0..), &[-0.0, 0.0, 0.5, 1.0, f64::INFINITY]);
+        assert_keys(query(..=-0.0), &[f64::NEG_INFINITY, -1.0, -0.0, 0.0]);
+        assert_keys(query(..0.0), &[f64::NEG_INFINITY, -1.0]);
+        assert_keys(query(-0.0..=0.0), &[-0.0, 0.0]);
+        assert_keys(query(0.0..0.0), &[]);
+        assert_keys(query((Bound::Excluded(-0.0), Bound::Included(0.5))), &[0.5]);
+    }
+
+    #[test]
+    fn test_numeric_range_nans() {
+        assert_keys(
+            query(..),
+            &[f64::NEG_INFINITY, -1.0, -0.0, 0.0, 0.5, 1.0, f64::INFINITY],
+        );
+        assert_keys(
+            query(NumericRange::new(0.5..).include_nans()),
+            &[0.5, 1.0, f64::INFINITY, f64::NAN],
+        );
+        assert_keys(
+            query(NumericRange::new(..-1.0).include_nans()),
+            &[-f64::NAN, f64::NEG_INFINITY],
+        );
+        assert_keys(query(NumericRange::new(0.5..1.0).include_nans()), &[0.5]);
+        assert_keys(query(f64::NAN..), &[]);
+        assert_keys(query(..=f64::NAN), &[]);
+        assert_keys(
+            query((Bound::Excluded(f64::INFINITY), Bound::Unbounded)),
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_numeric_range_empty() {
+        assert!(numeric_range(1.0..0.5).is_empty());
+        assert!(numeric_range(0.5..0.5).is_empty());
+        assert!(!numeric_range(0.5..=0.5).is_empty());
+        assert!(
+            numeric_range((Bound::Excluded(0.5), Bound::Excluded(0.5_f64.next_up()))).is_empty()
+        );
+        assert_keys(query(1.0..0.5), &[]);
+
+        let map: BTreeMap<TotalF64, ()> = BTreeMap::new();
+        assert_eq!(map.range(numeric_range(1.0..0.5)).count(), 0);
+    }
+}
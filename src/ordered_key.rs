@@ -0,0 +1,160 @@
+use core::fmt;
+
+use crate::{TotalF32, TotalF64};
+
+/// A `TotalF64` stored in its normalised form, for containers which compare keys constantly.
+///
+/// `Ord` for `TotalF64` normalises both operands on every comparison, whereas an `OrderedKey64`
+/// does this once on construction, so comparing and hashing are plain `i64` operations.
+/// The ordering, equality and hash are identical to those of the `TotalF64` it came from, so
+/// containers can switch between the two without changing behaviour.
+///
+/// ```rust
+/// use total_float_wrap::{OrderedKey64, TotalF64};
+///
+/// let mut keys: Vec<OrderedKey64> = [2.0, -0.0, f64::NAN, 0.0].map(OrderedKey64::from).into();
+/// keys.sort();
+///
+/// let sorted: Vec<TotalF64> = keys.into_iter().map(TotalF64::from).collect();
+/// assert_eq!(sorted, [-0.0, 0.0, 2.0, f64::NAN].map(TotalF64));
+/// ```
+#[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OrderedKey64(i64);
+
+impl OrderedKey64 {
+    /// Returns the float this key was built from, bit for bit
+    pub fn to_f64(self) -> f64 {
+        TotalF64::from_normalised(self.0).0
+    }
+}
+
+impl fmt::Debug for OrderedKey64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OrderedKey64").field(&self.to_f64()).finish()
+    }
+}
+
+impl From<TotalF64> for OrderedKey64 {
+    fn from(f: TotalF64) -> Self {
+        OrderedKey64(f.normalise())
+    }
+}
+
+impl From<OrderedKey64> for TotalF64 {
+    fn from(key: OrderedKey64) -> Self {
+        TotalF64::from_normalised(key.0)
+    }
+}
+
+impl From<f64> for OrderedKey64 {
+    fn from(f: f64) -> Self {
+        TotalF64(f).into()
+    }
+}
+
+impl From<OrderedKey64> for f64 {
+    fn from(key: OrderedKey64) -> Self {
+        key.to_f64()
+    }
+}
+
+/// A `TotalF32` stored in its normalised form, see [`OrderedKey64`].
+#[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OrderedKey32(i32);
+
+impl OrderedKey32 {
+    /// Returns the float this key was built from, bit for bit
+    pub fn to_f32(self) -> f32 {
+        TotalF32::from_normalised(self.0).0
+    }
+}
+
+impl fmt::Debug for OrderedKey32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OrderedKey32").field(&self.to_f32()).finish()
+    }
+}
+
+impl From<TotalF32> for OrderedKey32 {
+    fn from(f: TotalF32) -> Self {
+        OrderedKey32(f.normalise())
+    }
+}
+
+impl From<OrderedKey32> for TotalF32 {
+    fn from(key: OrderedKey32) -> Self {
+        TotalF32::from_normalised(key.0)
+    }
+}
+
+impl From<f32> for OrderedKey32 {
+    fn from(f: f32) -> Self {
+        TotalF32(f).into()
+    }
+}
+
+impl From<OrderedKey32> for f32 {
+    fn from(key: OrderedKey32) -> Self {
+        key.to_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+
+    const F64S: [f64; 9] = [
+        -f64::NAN,
+        f64::NEG_INFINITY,
+        -1.5,
+        -0.0,
+        0.0,
+        f64::MIN_POSITIVE,
+        1.5,
+        f64::INFINITY,
+        f64::NAN,
+    ];
+
+    #[test]
+    fn test_ordered_key64_matches_total_f64() {
+        let state = RandomState::new();
+        for a in F64S {
+            let key = OrderedKey64::from(a);
+            assert_eq!(key.to_f64().to_bits(), a.to_bits());
+            assert_eq!(TotalF64::from(key), TotalF64(a));
+            assert_eq!(state.hash_one(key), state.hash_one(TotalF64(a)));
+
+            for b in F64S {
+                assert_eq!(key.cmp(&b.into()), TotalF64(a).cmp(&TotalF64(b)), "{a} {b}");
+            }
+        }
+
+        let payload = f64::from_bits(f64::NAN.to_bits() | 0xbeef);
+        assert_eq!(
+            OrderedKey64::from(payload).to_f64().to_bits(),
+            payload.to_bits()
+        );
+        assert_eq!(
+            format!("{:?}", OrderedKey64::from(-0.0)),
+            "OrderedKey64(-0.0)"
+        );
+    }
+
+    #[test]
+    fn test_ordered_key32_matches_total_f32() {
+        let state = RandomState::new();
+        let f32s = F64S.map(|f| f as f32);
+        for a in f32s {
+            let key = OrderedKey32::from(a);
+            assert_eq!(key.to_f32().to_bits(), a.to_bits());
+            assert_eq!(TotalF32::from(key), TotalF32(a));
+            assert_eq!(state.hash_one(key), state.hash_one(TotalF32(a)));
+
+            for b in f32s {
+                assert_eq!(key.cmp(&b.into()), TotalF32(a).cmp(&TotalF32(b)), "{a} {b}");
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use core::hash::{Hash, Hasher};
+
+use crate::{TotalF32, TotalF64, TotalFloat, TotalNumber};
+
+/// The modulus CPython reduces numeric hashes by on 64 bit platforms, 2^61 - 1
+const MODULUS: u64 = (1 << 61) - 1;
+
+/// The hash CPython gives to positive infinity
+const HASH_INF: i64 = 314159;
+
+/// Applies the sign to a reduced hash, CPython reserves -1 as an error value so it becomes -2
+fn signed(negative: bool, reduced: u64) -> i64 {
+    let hash = if negative {
+        -(reduced as i64)
+    } else {
+        reduced as i64
+    };
+
+    if hash == -1 {
+        -2
+    } else {
+        hash
+    }
+}
+
+/// Computes the hash CPython gives to the integer `i` on 64 bit platforms.
+///
+/// This is `i` reduced modulo 2^61 - 1, keeping the sign, with -1 mapped to -2.
+pub fn py_hash_int(i: i128) -> i64 {
+    signed(i < 0, (i.unsigned_abs() % MODULUS as u128) as u64)
+}
+
+/// Computes the hash CPython gives to the integer `u` on 64 bit platforms, see [`py_hash_int`].
+pub fn py_hash_uint(u: u128) -> i64 {
+    signed(false, (u % MODULUS as u128) as u64)
+}
+
+/// Computes the hash CPython gives to the float `f` on 64 bit platforms.
+///
+/// Finite floats hash to their exact value reduced modulo 2^61 - 1, the same way integers do,
+/// so an integral float hashes equal to the integer: `py_hash_f64(3.0) == py_hash_int(3)`.
+/// Infinities hash to ±314159 and both zeros hash to 0.
+///
+/// Since Python 3.10 CPython hashes NaNs by object identity, which cannot be reproduced,
+/// so every NaN hashes to 0 as it did in earlier versions.
+pub fn py_hash_f64(f: f64) -> i64 {
+    if f.is_nan() {
+        return 0;
+    }
+    if f.is_infinite() {
+        return signed(f < 0.0, HASH_INF as u64);
+    }
+
+    // decompose the float into mantissa * 2^exponent with an integer mantissa
+    let bits = f.to_bits();
+    let biased_exponent = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = bits & ((1 << 52) - 1);
+    let (mantissa, exponent) = if biased_exponent == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << 52), biased_exponent - 1075)
+    };
+
+    // 2^61 is 1 modulo 2^61 - 1, so multiplying by 2^exponent is the same as multiplying by
+    // 2^(exponent mod 61), which works for negative exponents too
+    let shift = exponent.rem_euclid(61);
+    let reduced = ((mantissa as u128) << shift) % MODULUS as u128;
+
+    signed(f < 0.0, reduced as u64)
+}
+
+/// Types which can be hashed the way CPython hashes the equivalent Python number.
+pub trait PyHash {
+    /// Returns the value CPython's `hash()` gives for the equivalent Python number.
+    fn py_hash(&self) -> i64;
+}
+
+impl PyHash for TotalF64 {
+    fn py_hash(&self) -> i64 {
+        py_hash_f64(self.0)
+    }
+}
+
+impl PyHash for TotalF32 {
+    fn py_hash(&self) -> i64 {
+        // widening is exact, so this is the hash of the same value
+        py_hash_f64(self.0 as f64)
+    }
+}
+
+impl PyHash for TotalFloat {
+    fn py_hash(&self) -> i64 {
+        self.widen().py_hash()
+    }
+}
+
+impl PyHash for TotalNumber {
+    fn py_hash(&self) -> i64 {
+        match self {
+            TotalNumber::Int(i) => py_hash_int(*i),
+            TotalNumber::F32(f) => f.py_hash(),
+            TotalNumber::F64(f) => f.py_hash(),
+        }
+    }
+}
+
+/// Wrapper which hashes the inner value with its CPython compatible numeric hash.
+///
+/// Equality is that of the wrapped type, only the hash changes: equal numbers of different
+/// types, such as `TotalNumber::Int(1)` and `TotalNumber::F64(1.0)`, feed the same `i64` to
+/// the `Hasher` just as `hash(1) == hash(1.0)` in Python.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PyHashed<T>(pub T);
+
+impl<T: PyHash> Hash for PyHashed<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // numerically equal values have equal python hashes, and the wrapped equality
+        // never considers numerically different values equal
+        state.write_i64(self.0.py_hash());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_py_hash_f64() {
+        // values from CPython 3.11 on x86_64
+        let cases = [
+            (1.0, 1),
+            (1.5, 1152921504606846977),
+            (0.5, 1152921504606846976),
+            (-1.0, -2),
+            (-1.5, -1152921504606846977),
+            (-2.5, -1152921504606846978),
+            (1e100, 1822893315824342674),
+            (1e-300, 482449582752280463),
+            (5e-324, 16777216),
+            (-0.0, 0),
+            (f64::INFINITY, 314159),
+            (f64::NEG_INFINITY, -314159),
+            (2.305843009213694e18, 1),
+            (1.8446744073709552e19, 8),
+            (core::f64::consts::PI, 326490430436040707),
+            (1e19, 776627963145224196),
+        ];
+
+        for (f, expected) in cases {
+            assert_eq!(py_hash_f64(f), expected, "hash({f:?})");
+        }
+        assert_eq!(TotalF32(0.1).py_hash(), 230584304357343232);
+        assert_eq!(TotalF64(f64::NAN).py_hash(), 0);
+    }
+
+    #[test]
+    fn test_py_hash_int() {
+        let cases = [
+            (1, 1),
+            (-1, -2),
+            (-2, -2),
+            (2305843009213693951, 0),
+            (2305843009213693952, 1),
+            (-2305843009213693952, -2),
+            (18446744073709551616, 8),
+            (i128::MAX, 31),
+            (i128::MIN, -32),
+            (12345678901234567890, 816463855166098135),
+        ];
+
+        for (i, expected) in cases {
+            assert_eq!(py_hash_int(i), expected, "hash({i})");
+        }
+        assert_eq!(py_hash_uint(u128::MAX), 63);
+    }
+
+    #[test]
+    fn test_py_hash_integral_floats_match_ints() {
+        for i in [0_i64, 7, -7, 1 << 53, -(1 << 62), i64::MIN] {
+            assert_eq!(TotalF64(i as f64).py_hash(), py_hash_int(i.into()));
+            assert_eq!(
+                TotalNumber::from(i).py_hash(),
+                TotalNumber::from(i as f64).py_hash()
+            );
+        }
+    }
+
+    #[test]
+    fn test_py_hashed_collides() {
+        use std::collections::hash_map::RandomState;
+        use std::hash::BuildHasher;
+
+        let state = RandomState::new();
+        let int = PyHashed(TotalNumber::from(3));
+        let float = PyHashed(TotalNumber::from(3.0_f32));
+        assert_eq!(state.hash_one(int), state.hash_one(float));
+        assert_ne!(int, float);
+    }
+}
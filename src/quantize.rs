@@ -0,0 +1,618 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use crate::{FloatBuildHasher, TotalF64};
+
+/// Snaps floats to a grid, so that values which differ only by rounding error compare equal.
+///
+/// Every grid is symmetric about zero and contains zero, and snapping is monotonic, so if
+/// `a <= b` then `quantize(a) <= quantize(b)`. Snapping rounds to the nearest grid value, and
+/// `-0.0` snaps to `0.0`. Finite values whose nearest grid value is too large for a float snap to
+/// the largest finite one, and infinities and NaNs are left as they are.
+///
+/// ```rust
+/// use total_float_wrap::Quantizer;
+///
+/// let grid = Quantizer::significant_digits(12).unwrap();
+/// assert_ne!(0.1 + 0.2, 0.3);
+/// assert_eq!(grid.quantize(0.1 + 0.2), grid.quantize(0.3));
+/// assert!(Quantizer::step(-1.0).is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quantizer(Grid);
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Grid {
+    Step(f64),
+    SignificantDigits(u32),
+    MantissaBits(u32),
+}
+
+/// Error returned when a [`Quantizer`] is created with a parameter out of range.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum QuantizerError {
+    /// The step is not positive and finite.
+    InvalidStep,
+    /// The number of significant digits is not from 1 to 17.
+    InvalidSignificantDigits,
+    /// The number of mantissa bits is more than 52.
+    InvalidMantissaBits,
+}
+
+impl fmt::Display for QuantizerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            QuantizerError::InvalidStep => "quantizer step is not positive and finite",
+            QuantizerError::InvalidSignificantDigits => {
+                "quantizer significant digit count is not from 1 to 17"
+            }
+            QuantizerError::InvalidMantissaBits => "quantizer mantissa bit count is more than 52",
+        })
+    }
+}
+
+impl std::error::Error for QuantizerError {}
+
+/// A float snapped to the grid of a [`Quantizer`], compared, ordered and hashed by its
+/// snapped value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Quantized(TotalF64);
+
+impl Quantized {
+    /// Returns the snapped value
+    pub fn value(self) -> TotalF64 {
+        self.0
+    }
+}
+
+impl From<Quantized> for TotalF64 {
+    fn from(value: Quantized) -> Self {
+        value.0
+    }
+}
+
+impl From<Quantized> for f64 {
+    fn from(value: Quantized) -> Self {
+        value.0 .0
+    }
+}
+
+impl Quantizer {
+    /// Creates a grid of the multiples of `step`, which must be positive and finite.
+    ///
+    /// Values more than 2^53 steps from zero, where the multiples are closer together than the
+    /// floats, are left as they are.
+    pub fn step(step: f64) -> Result<Self, QuantizerError> {
+        if step > 0.0 && step.is_finite() {
+            Ok(Quantizer(Grid::Step(step)))
+        } else {
+            Err(QuantizerError::InvalidStep)
+        }
+    }
+
+    /// Creates a grid of the values with `digits` significant decimal digits, from 1 to 17.
+    ///
+    /// Values are rounded exactly when the power of ten scaling them to an integer mantissa is
+    /// exact, which covers up to 15 digits of magnitudes from 1e-8 to 1e22. Beyond that the grid
+    /// value may be a rounding error away from the nearest one.
+    pub fn significant_digits(digits: u32) -> Result<Self, QuantizerError> {
+        if (1..=17).contains(&digits) {
+            Ok(Quantizer(Grid::SignificantDigits(digits)))
+        } else {
+            Err(QuantizerError::InvalidSignificantDigits)
+        }
+    }
+
+    /// Creates a grid of the values with `bits` explicit mantissa bits, from 0 to 52.
+    pub fn mantissa_bits(bits: u32) -> Result<Self, QuantizerError> {
+        if bits <= 52 {
+            Ok(Quantizer(Grid::MantissaBits(bits)))
+        } else {
+            Err(QuantizerError::InvalidMantissaBits)
+        }
+    }
+
+    /// Snaps the value to the grid
+    pub fn quantize(&self, value: f64) -> Quantized {
+        if !value.is_finite() {
+            return Quantized(TotalF64(value));
+        }
+
+        let snapped = match self.0 {
+            // beyond 2^53 steps the grid is finer than the floats
+            Grid::Step(step) if (value / step).abs() >= EXACT_STEPS => value,
+            Grid::Step(step) => {
+                let snapped = (value / step).round() * step;
+                if snapped.is_finite() {
+                    snapped
+                } else {
+                    // the nearest multiple is beyond the floats, so take the largest below them
+                    let top = (f64::MAX / step).floor();
+                    let largest = if top * step <= f64::MAX {
+                        top * step
+                    } else {
+                        (top - 1.0) * step
+                    };
+                    largest.copysign(value)
+                }
+            }
+            Grid::SignificantDigits(digits) => {
+                let (mut mantissa, exponent) = decimal(value, digits);
+                let mut snapped = scale(mantissa as f64, exponent);
+                // rounded up beyond the floats, so step down to the largest grid value below
+                // them, which the inexact power of ten can put a few mantissas down
+                while !snapped.is_finite() {
+                    mantissa -= mantissa.signum();
+                    snapped = scale(mantissa as f64, exponent);
+                }
+                snapped
+            }
+            Grid::MantissaBits(bits) => {
+                let unit = mantissa_unit(bits);
+                let magnitude = value.to_bits() & !SIGN_BIT;
+                // a carry into the infinite exponent is held at the largest grid value
+                let rounded = ((magnitude + unit / 2) & !(unit - 1)).min(INFINITY_BITS - unit);
+                f64::from_bits(rounded | (value.to_bits() & SIGN_BIT))
+            }
+        };
+        Quantized(TotalF64(canonical(snapped)))
+    }
+
+    /// Returns the cells below and above `cell` along with the cell itself, in increasing order.
+    ///
+    /// A value within half a cell of a value in `cell` is in one of these, so looking in all
+    /// three never misses a match which was snapped across a cell edge.
+    /// Infinite and NaN cells have no neighbours, and are returned three times.
+    pub fn neighbours(&self, cell: Quantized) -> [Quantized; 3] {
+        let value = cell.0 .0;
+        [canonical(-self.step_up(-value)), value, self.step_up(value)]
+            .map(|v| Quantized(TotalF64(v)))
+    }
+
+    /// Iterates in increasing order over every cell which could contain a value within
+    /// `tolerance` of `value`.
+    ///
+    /// The number of cells is about `2 * tolerance` divided by the cell size, which for
+    /// [`significant_digits`](Quantizer::significant_digits) and
+    /// [`mantissa_bits`](Quantizer::mantissa_bits) grids grows without bound near zero.
+    ///
+    /// Panics if `tolerance` is negative or NaN.
+    pub fn cells_within(&self, value: f64, tolerance: f64) -> CellsWithin {
+        assert!(tolerance >= 0.0, "invalid tolerance {tolerance}");
+        let (low, high) = if value.is_finite() {
+            (value - tolerance, value + tolerance)
+        } else {
+            (value, value)
+        };
+
+        CellsWithin {
+            quantizer: *self,
+            next: Some(self.quantize(low)),
+            last: self.quantize(high),
+        }
+    }
+
+    /// Returns the smallest grid value greater than the snapped `value`, or `value` if it is not
+    /// finite.
+    fn step_up(&self, value: f64) -> f64 {
+        if !value.is_finite() {
+            return value;
+        }
+
+        let next = match self.0 {
+            Grid::Step(step) => ((value / step).round() + 1.0) * step,
+            Grid::SignificantDigits(_) if value == 0.0 => f64::from_bits(1),
+            Grid::SignificantDigits(digits) => {
+                let (mantissa, exponent) = decimal(value, digits);
+                let smallest = 10_i64.pow(digits - 1);
+                let next = mantissa + 1;
+                if next == smallest * 10 {
+                    scale(smallest as f64, exponent + 1)
+                } else if next < 0 && -next < smallest {
+                    scale(-(smallest * 10 - 1) as f64, exponent - 1)
+                } else {
+                    scale(next as f64, exponent)
+                }
+            }
+            Grid::MantissaBits(bits) => {
+                let unit = mantissa_unit(bits);
+                let magnitude = value.abs().to_bits();
+                if value >= 0.0 {
+                    f64::from_bits(magnitude + unit)
+                } else {
+                    -f64::from_bits(magnitude - unit)
+                }
+            }
+        };
+        // steps too small to represent, among subnormals or beyond 2^53 steps, round back
+        let next = self.quantize(next).0 .0;
+        if next > value {
+            next
+        } else {
+            self.quantize(value.next_up()).0 .0
+        }
+    }
+}
+
+/// Iterator over the cells returned by [`Quantizer::cells_within`].
+#[derive(Debug, Clone)]
+pub struct CellsWithin {
+    quantizer: Quantizer,
+    next: Option<Quantized>,
+    last: Quantized,
+}
+
+impl Iterator for CellsWithin {
+    type Item = Quantized;
+
+    fn next(&mut self) -> Option<Quantized> {
+        let cell = self.next.filter(|&cell| cell <= self.last)?;
+        let up = self.quantizer.neighbours(cell)[2];
+        self.next = (up > cell).then_some(up);
+        Some(cell)
+    }
+}
+
+const SIGN_BIT: u64 = 1 << 63;
+
+const INFINITY_BITS: u64 = 0x7ff0_0000_0000_0000;
+
+/// The number of steps beyond which consecutive floats are further apart than a step
+const EXACT_STEPS: f64 = (1_u64 << 53) as f64;
+
+/// Turns `-0.0` into `0.0`
+fn canonical(value: f64) -> f64 {
+    if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+/// Returns the spacing, in bits, of floats with the given number of explicit mantissa bits
+fn mantissa_unit(bits: u32) -> u64 {
+    1 << (52 - bits)
+}
+
+/// The powers of ten which are exactly representable
+const POWERS_OF_TEN: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+/// Returns `value * 10^exponent`, correctly rounded when the power of ten is exact
+fn scale(mut value: f64, mut exponent: i32) -> f64 {
+    // larger powers are applied in exact steps, so that they never overflow on their own
+    while exponent > 22 {
+        value *= 1e22;
+        exponent -= 22;
+    }
+    while exponent < -22 {
+        value /= 1e22;
+        exponent += 22;
+    }
+
+    let power = POWERS_OF_TEN[exponent.unsigned_abs() as usize];
+    if exponent >= 0 {
+        value * power
+    } else {
+        value / power
+    }
+}
+
+/// Returns `value * 10^exponent` rounded to the nearest integer, with ties to even as when
+/// formatting
+fn round_scaled(value: f64, exponent: i32) -> i64 {
+    let scaled = scale(value, exponent);
+    let rounded = scaled.round();
+    if (rounded - scaled).abs() == 0.5 && exponent.abs() <= 22 {
+        // scaling rounded, so the exact value may fall just short of the tie; the error of a
+        // product or the remainder of a quotient is exact, and has the sign of the shortfall
+        let power = POWERS_OF_TEN[exponent.unsigned_abs() as usize];
+        let error = if exponent >= 0 {
+            value.mul_add(power, -scaled)
+        } else {
+            (-scaled).mul_add(power, value)
+        };
+        if error == 0.0 {
+            return ((scaled / 2.0).round() * 2.0) as i64;
+        } else if (error > 0.0) != (scaled > 0.0) {
+            return scaled.trunc() as i64;
+        }
+    }
+    rounded as i64
+}
+
+/// Splits the value, rounded to `digits` significant digits, into an integer mantissa of exactly
+/// `digits` digits and a decimal exponent, or zero.
+fn decimal(value: f64, digits: u32) -> (i64, i32) {
+    if value == 0.0 {
+        return (0, 0);
+    }
+
+    let smallest = 10_i64.pow(digits - 1);
+    // the logarithm can be a decade out near powers of ten, which the mantissa shows
+    let mut exponent = value.abs().log10().floor() as i32 - (digits as i32 - 1);
+    let mut mantissa = round_scaled(value, -exponent);
+    if mantissa.abs() < smallest {
+        exponent -= 1;
+        mantissa = round_scaled(value, -exponent);
+    } else if mantissa.abs() >= smallest * 10 {
+        exponent += 1;
+        mantissa = round_scaled(value, -exponent);
+    }
+    if mantissa.abs() == smallest * 10 {
+        // rounded up into the next decade
+        mantissa /= 10;
+        exponent += 1;
+    }
+    (mantissa, exponent)
+}
+
+/// Deduplicates points whose coordinates are all within `tolerance` of each other.
+///
+/// Each point is merged into the first earlier kept point it is close to, so the result is
+/// deterministic but depends on the order of the input. Coordinates which are not finite only
+/// match coordinates with identical bits.
+///
+/// Returns the kept points, and the index into them of every input point.
+///
+/// Panics if `tolerance` is not positive, or twice it is not finite.
+///
+/// ```rust
+/// use total_float_wrap::weld_points;
+///
+/// let triangles = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1e-12], [0.0, 1.0 - 1e-12], [1.0, 1.0]];
+/// let (vertices, indices) = weld_points(&triangles, 1e-9);
+/// assert_eq!(vertices, [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]);
+/// assert_eq!(indices, [0, 1, 2, 1, 2, 3]);
+/// ```
+pub fn weld_points<const N: usize>(
+    points: &[[f64; N]],
+    tolerance: f64,
+) -> (Vec<[f64; N]>, Vec<usize>) {
+    // cells twice as wide as the tolerance, so a match is always in one of the neighbouring cells
+    let grid = Quantizer::step(2.0 * tolerance)
+        .unwrap_or_else(|_| panic!("invalid tolerance {tolerance}"));
+    let mut cells: HashMap<[Quantized; N], Vec<usize>, FloatBuildHasher> = HashMap::default();
+    let mut welded: Vec<[f64; N]> = Vec::new();
+    let mut indices = Vec::with_capacity(points.len());
+
+    let close = |a: &[f64; N], b: &[f64; N]| {
+        a.iter()
+            .zip(b)
+            .all(|(&x, &y)| TotalF64(x) == TotalF64(y) || (x - y).abs() <= tolerance)
+    };
+
+    for point in points {
+        let home = point.map(|x| grid.quantize(x));
+        let neighbours = home.map(|cell| grid.neighbours(cell));
+
+        let mut found = None;
+        // visit the 3^N neighbouring cells, counting in base 3 over the coordinates
+        for n in 0..3_usize.pow(N as u32) {
+            let mut digits = n;
+            let cell: [Quantized; N] = core::array::from_fn(|axis| {
+                let digit = digits % 3;
+                digits /= 3;
+                neighbours[axis][digit]
+            });
+
+            let candidates = cells.get(&cell).into_iter().flatten();
+            if let Some(&index) = candidates.filter(|&&i| close(&welded[i], point)).min() {
+                found = Some(found.map_or(index, |f: usize| f.min(index)));
+            }
+        }
+
+        let index = found.unwrap_or_else(|| {
+            welded.push(*point);
+            cells.entry(home).or_default().push(welded.len() - 1);
+            welded.len() - 1
+        });
+        indices.push(index);
+    }
+
+    (welded, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(cells: impl IntoIterator<Item = Quantized>) -> Vec<f64> {
+        cells.into_iter().map(f64::from).collect()
+    }
+
+    #[test]
+    fn test_quantize() {
+        let step = Quantizer::step(0.25).unwrap();
+        assert_eq!(f64::from(step.quantize(0.3)), 0.25);
+        assert_eq!(f64::from(step.quantize(-0.375)), -0.5);
+        assert_eq!(step.quantize(-0.1).value().0.to_bits(), 0);
+        assert_eq!(step.quantize(-0.0), step.quantize(0.0));
+        assert_eq!(
+            step.quantize(f64::NEG_INFINITY).value(),
+            TotalF64(f64::NEG_INFINITY)
+        );
+
+        let digits = Quantizer::significant_digits(3).unwrap();
+        assert_eq!(f64::from(digits.quantize(123456.0)), 123000.0);
+        assert_eq!(f64::from(digits.quantize(-0.0012345)), -0.00123);
+        assert_eq!(digits.quantize(0.1 + 0.2), digits.quantize(0.3));
+
+        let bits = Quantizer::mantissa_bits(2).unwrap();
+        assert_eq!(f64::from(bits.quantize(1.3)), 1.25);
+        assert_eq!(f64::from(bits.quantize(-1.9)), -2.0);
+        assert_eq!(
+            f64::from(Quantizer::mantissa_bits(52).unwrap().quantize(0.1)),
+            0.1
+        );
+
+        assert_eq!(Quantizer::step(0.0), Err(QuantizerError::InvalidStep));
+        assert_eq!(Quantizer::step(f64::NAN), Err(QuantizerError::InvalidStep));
+        assert_eq!(
+            Quantizer::significant_digits(0),
+            Err(QuantizerError::InvalidSignificantDigits)
+        );
+        assert_eq!(
+            Quantizer::significant_digits(18),
+            Err(QuantizerError::InvalidSignificantDigits)
+        );
+        assert_eq!(
+            Quantizer::mantissa_bits(53),
+            Err(QuantizerError::InvalidMantissaBits)
+        );
+    }
+
+    #[test]
+    fn test_quantize_stays_finite() {
+        let max = f64::MAX;
+        let step = Quantizer::step(1e-9).unwrap();
+        assert_eq!(f64::from(step.quantize(1e300)), 1e300);
+        assert_eq!(f64::from(step.quantize(-max)), -max);
+        assert_eq!(
+            f64::from(Quantizer::step(1e308).unwrap().quantize(max)),
+            1e308
+        );
+        // the step rounds up, so three of them are beyond the floats
+        let step = Quantizer::step(max / 3.0).unwrap();
+        assert_eq!(3.0 * (max / 3.0), f64::INFINITY);
+        assert_eq!(f64::from(step.quantize(-max)), -2.0 * (max / 3.0));
+
+        // 10^308 itself is a rounding error out, as the power of ten is inexact
+        let digits = Quantizer::significant_digits(1).unwrap();
+        assert_eq!(digits.quantize(max), digits.quantize(1e308));
+        assert_eq!(digits.quantize(-max), digits.quantize(-1e308));
+        assert!((f64::from(digits.quantize(max)) - 1e308).abs() <= 1e308 * f64::EPSILON);
+        let digits = Quantizer::significant_digits(17).unwrap();
+        assert!(f64::from(digits.quantize(max)).is_finite());
+
+        for bits in [0, 10, 52] {
+            let grid = Quantizer::mantissa_bits(bits).unwrap();
+            let largest = f64::from(grid.quantize(max));
+            assert!(largest.is_finite() && largest > max / 2.0, "{bits}");
+            assert_eq!(f64::from(grid.quantize(-max)), -largest);
+        }
+        assert_eq!(
+            f64::from(Quantizer::mantissa_bits(0).unwrap().quantize(max)),
+            2_f64.powi(1023)
+        );
+        assert_eq!(
+            f64::from(Quantizer::mantissa_bits(52).unwrap().quantize(max)),
+            max
+        );
+
+        let inf = Quantizer::step(1.0).unwrap().quantize(f64::INFINITY);
+        assert_eq!(f64::from(inf), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_significant_digits_round_exactly() {
+        // formatting rounds the exact decimal expansion, and parsing rounds correctly
+        let reference = |value: f64, digits: u32| -> f64 {
+            format!("{:.*e}", digits as usize - 1, value)
+                .parse()
+                .unwrap()
+        };
+
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        for _ in 0..20_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let digits = (state % 15) as u32 + 1;
+            let exponent = (state >> 8) % 30;
+            let value = ((state >> 11) as f64 / (1_u64 << 53) as f64 + 1.0)
+                * 10_f64.powi(exponent as i32 - 8);
+            let value = if state & 1 == 0 { value } else { -value };
+
+            let grid = Quantizer::significant_digits(digits).unwrap();
+            assert_eq!(
+                f64::from(grid.quantize(value)),
+                reference(value, digits),
+                "{value} {digits}"
+            );
+        }
+
+        // products which round onto a tie, and exact ties
+        let grid = Quantizer::significant_digits(1).unwrap();
+        assert_eq!(f64::from(grid.quantize(0.15)), 0.1);
+        assert_eq!(f64::from(grid.quantize(-0.25)), -0.2);
+        assert_eq!(f64::from(grid.quantize(9.5)), 10.0);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let step = Quantizer::step(0.5).unwrap();
+        assert_eq!(
+            values(step.neighbours(step.quantize(0.1))),
+            [-0.5, 0.0, 0.5]
+        );
+
+        let digits = Quantizer::significant_digits(2).unwrap();
+        assert_eq!(
+            values(digits.neighbours(digits.quantize(1.0))),
+            [0.99, 1.0, 1.1]
+        );
+        assert_eq!(
+            values(digits.neighbours(digits.quantize(-9.9))),
+            [-10.0, -9.9, -9.8]
+        );
+
+        let bits = Quantizer::mantissa_bits(1).unwrap();
+        assert_eq!(values(bits.neighbours(bits.quantize(2.0))), [1.5, 2.0, 3.0]);
+        let [below, zero, above] = bits.neighbours(bits.quantize(0.0)).map(f64::from);
+        assert_eq!((zero, below, above > 0.0), (0.0, -above, true));
+
+        let nan = step.quantize(f64::NAN);
+        assert_eq!(step.neighbours(nan), [nan; 3]);
+    }
+
+    #[test]
+    fn test_cells_within_never_misses() {
+        let grids = [
+            Quantizer::step(0.1).unwrap(),
+            Quantizer::significant_digits(2).unwrap(),
+            Quantizer::mantissa_bits(3).unwrap(),
+        ];
+        let tolerance = 0.05;
+        for grid in grids {
+            for i in -60..60 {
+                let x = 0.5 + f64::from(i) * 0.0073;
+                let cells: Vec<Quantized> = grid.cells_within(x, tolerance).collect();
+                assert!(cells.windows(2).all(|w| w[0] < w[1]));
+                for j in -20..=20 {
+                    let y = x + tolerance * f64::from(j) / 20.0;
+                    assert!(cells.contains(&grid.quantize(y)), "{grid:?} {x} {y}");
+                }
+            }
+        }
+
+        let cells: Vec<Quantized> = Quantizer::step(1.0)
+            .unwrap()
+            .cells_within(f64::NAN, 1.0)
+            .collect();
+        assert_eq!(cells.len(), 1);
+    }
+
+    #[test]
+    fn test_weld_points() {
+        let points = [
+            [0.0, 0.0, 0.0],
+            [0.1, 0.0, 0.0],
+            [0.0999, 0.0001, -0.0],
+            [0.0, 0.0, 0.11],
+            [f64::NAN, 0.0, 0.0],
+            [f64::NAN, 0.0, 0.0],
+            [0.19, 0.0, 0.0],
+        ];
+        let (welded, indices) = weld_points(&points, 0.001);
+        assert_eq!(welded.len(), 5);
+        assert_eq!(indices, [0, 1, 1, 2, 3, 3, 4]);
+
+        // chains are not merged transitively, only into a kept point
+        let (welded, indices) = weld_points(&[[0.0], [0.6], [1.2]], 1.0);
+        assert_eq!(welded, [[0.0], [1.2]]);
+        assert_eq!(indices, [0, 0, 1]);
+    }
+}
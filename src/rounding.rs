@@ -0,0 +1,30 @@
+use core::fmt;
+
+/// The rounding direction used when a value cannot be represented exactly in the target type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum RoundingMode {
+    /// Round to the nearest representable value, ties go to the value with an even last digit.
+    #[default]
+    NearestEven,
+    /// Round towards positive infinity.
+    TowardPositive,
+    /// Round towards negative infinity.
+    TowardNegative,
+    /// Round towards zero, discarding the excess precision.
+    TowardZero,
+    /// Do not round at all, return an error if the value is not exactly representable.
+    Exact,
+}
+
+/// Error returned when [`RoundingMode::Exact`] is requested for a value which cannot be
+/// represented exactly in the target type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InexactError;
+
+impl fmt::Display for InexactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("value is not exactly representable in the target type")
+    }
+}
+
+impl core::error::Error for InexactError {}
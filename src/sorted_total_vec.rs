@@ -0,0 +1,256 @@
+use core::cmp::Ordering;
+use core::ops::{Bound, RangeBounds};
+
+use crate::TotalF64;
+
+/// A sorted set of `TotalF64`, stored in a single vector.
+///
+/// Values are kept as their normalised integers, so every search compares plain `i64`s rather than
+/// re-normalising floats. This makes it a compact, cache friendly alternative to a
+/// `BTreeSet<TotalF64>` for data which is built once and queried often, insertion and removal
+/// are `O(n)`.
+///
+/// Ordering and equality follow the total order, as for `TotalF64`.
+///
+/// ```rust
+/// use total_float_wrap::{SortedTotalVec, TotalF64};
+///
+/// let values: SortedTotalVec = [3.0, -1.0, 2.0, 3.0].into_iter().map(TotalF64).collect();
+///
+/// assert_eq!(values.len(), 3);
+/// assert_eq!(values.rank(TotalF64(2.5)), 2);
+/// assert_eq!(values.select(0), Some(TotalF64(-1.0)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SortedTotalVec {
+    keys: Vec<i64>,
+}
+
+impl SortedTotalVec {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of values in the set
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns whether the set contains no values
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Adds a value to the set, returning whether it was not already present
+    pub fn insert(&mut self, value: TotalF64) -> bool {
+        let key = value.normalise();
+        match self.keys.binary_search(&key) {
+            Ok(_) => false,
+            Err(i) => {
+                self.keys.insert(i, key);
+                true
+            }
+        }
+    }
+
+    /// Removes a value from the set, returning whether it was present
+    pub fn remove(&mut self, value: TotalF64) -> bool {
+        match self.keys.binary_search(&value.normalise()) {
+            Ok(i) => {
+                self.keys.remove(i);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Returns whether the set contains the value
+    pub fn contains(&self, value: TotalF64) -> bool {
+        self.keys.binary_search(&value.normalise()).is_ok()
+    }
+
+    /// Returns the number of values in the set which are less than `value`
+    pub fn rank(&self, value: TotalF64) -> usize {
+        let key = value.normalise();
+        self.keys.partition_point(|&k| k < key)
+    }
+
+    /// Returns the `k`th smallest value in the set, counting from zero
+    pub fn select(&self, k: usize) -> Option<TotalF64> {
+        self.keys.get(k).copied().map(TotalF64::from_normalised)
+    }
+
+    /// Iterates over the values in ascending order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = TotalF64> + ExactSizeIterator + '_ {
+        self.keys.iter().copied().map(TotalF64::from_normalised)
+    }
+
+    /// Iterates over the values within `range` in ascending order.
+    ///
+    /// Any `RangeBounds<TotalF64>` can be used, including a [`NumericRange`](crate::NumericRange).
+    pub fn range<R: RangeBounds<TotalF64>>(
+        &self,
+        range: R,
+    ) -> impl DoubleEndedIterator<Item = TotalF64> + ExactSizeIterator + '_ {
+        let start = match range.start_bound() {
+            Bound::Included(s) => self.rank(*s),
+            Bound::Excluded(s) => {
+                let key = s.normalise();
+                self.keys.partition_point(|&k| k <= key)
+            }
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(e) => {
+                let key = e.normalise();
+                self.keys.partition_point(|&k| k <= key)
+            }
+            Bound::Excluded(e) => self.rank(*e),
+            Bound::Unbounded => self.keys.len(),
+        };
+
+        self.keys[start..end.max(start)]
+            .iter()
+            .copied()
+            .map(TotalF64::from_normalised)
+    }
+
+    /// Returns the union of the two sets, merging them in linear time
+    pub fn merge(&self, other: &Self) -> Self {
+        let (a, b) = (&self.keys, &other.keys);
+        let mut keys = Vec::with_capacity(a.len() + b.len());
+        let (mut i, mut j) = (0, 0);
+
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                Ordering::Less => {
+                    keys.push(a[i]);
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    keys.push(b[j]);
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    keys.push(a[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        keys.extend_from_slice(&a[i..]);
+        keys.extend_from_slice(&b[j..]);
+
+        Self { keys }
+    }
+}
+
+impl FromIterator<TotalF64> for SortedTotalVec {
+    /// Builds the set in bulk, sorting once rather than inserting each value
+    fn from_iter<I: IntoIterator<Item = TotalF64>>(iter: I) -> Self {
+        let mut keys: Vec<i64> = iter.into_iter().map(|v| v.normalise()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        Self { keys }
+    }
+}
+
+impl Extend<TotalF64> for SortedTotalVec {
+    fn extend<I: IntoIterator<Item = TotalF64>>(&mut self, iter: I) {
+        self.keys.extend(iter.into_iter().map(|v| v.normalise()));
+        self.keys.sort_unstable();
+        self.keys.dedup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric_range;
+
+    fn values(set: &SortedTotalVec) -> Vec<f64> {
+        set.iter().map(|v| v.0).collect()
+    }
+
+    #[test]
+    fn test_sorted_total_vec_insert_remove() {
+        let mut set = SortedTotalVec::new();
+        assert!(set.insert(TotalF64(1.0)));
+        assert!(set.insert(TotalF64(-0.0)));
+        assert!(set.insert(TotalF64(0.0)));
+        assert!(set.insert(TotalF64(f64::NAN)));
+        assert!(!set.insert(TotalF64(1.0)));
+        assert_eq!(set.len(), 4);
+
+        assert!(set.contains(TotalF64(-0.0)));
+        assert!(set.remove(TotalF64(-0.0)));
+        assert!(!set.contains(TotalF64(-0.0)));
+        assert!(set.contains(TotalF64(0.0)));
+        assert!(!set.remove(TotalF64(2.0)));
+
+        let last = set.select(2).unwrap();
+        assert!(last.0.is_nan());
+        assert_eq!(values(&set)[..2], [0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_sorted_total_vec_rank_select() {
+        let set: SortedTotalVec = [5.0, 1.0, 3.0, -2.0, 3.0]
+            .into_iter()
+            .map(TotalF64)
+            .collect();
+        assert_eq!(values(&set), [-2.0, 1.0, 3.0, 5.0]);
+
+        assert_eq!(set.rank(TotalF64(f64::NEG_INFINITY)), 0);
+        assert_eq!(set.rank(TotalF64(3.0)), 2);
+        assert_eq!(set.rank(TotalF64(3.5)), 3);
+        assert_eq!(set.rank(TotalF64(f64::NAN)), 4);
+
+        for k in 0..set.len() {
+            assert_eq!(set.rank(set.select(k).unwrap()), k);
+        }
+        assert_eq!(set.select(4), None);
+    }
+
+    #[test]
+    fn test_sorted_total_vec_range() {
+        let set: SortedTotalVec = [-1.0, -0.0, 0.0, 0.5, 1.0, f64::NAN]
+            .into_iter()
+            .map(TotalF64)
+            .collect();
+
+        let in_range: Vec<f64> = set
+            .range(TotalF64(0.0)..TotalF64(1.0))
+            .map(|v| v.0)
+            .collect();
+        assert_eq!(in_range, [0.0, 0.5]);
+
+        let in_range: Vec<f64> = set
+            .range((
+                Bound::Excluded(TotalF64(-1.0)),
+                Bound::Included(TotalF64(0.5)),
+            ))
+            .map(|v| v.0)
+            .collect();
+        assert_eq!(in_range, [-0.0, 0.0, 0.5]);
+
+        assert_eq!(set.range(numeric_range(0.0..)).len(), 4);
+        assert_eq!(set.range(numeric_range(2.0..1.0)).len(), 0);
+        assert_eq!(set.range(..).rev().nth(1), Some(TotalF64(1.0)));
+    }
+
+    #[test]
+    fn test_sorted_total_vec_merge() {
+        let a: SortedTotalVec = [1.0, 3.0, 5.0].into_iter().map(TotalF64).collect();
+        let b: SortedTotalVec = [0.0, 3.0, 6.0, 7.0].into_iter().map(TotalF64).collect();
+
+        let merged = a.merge(&b);
+        assert_eq!(values(&merged), [0.0, 1.0, 3.0, 5.0, 6.0, 7.0]);
+        assert_eq!(merged, b.merge(&a));
+
+        let mut extended = a.clone();
+        extended.extend(b.iter());
+        assert_eq!(extended, merged);
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+
+use crate::{FloatBuildHasher, TotalArray, TotalF64};
+
+/// Identifies a point inserted into a [`SpatialHashGrid`].
+///
+/// Handles of removed points are never reused for later points by the same grid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GridHandle {
+    slot: u32,
+    generation: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<const N: usize, T> {
+    generation: u32,
+    entry: Option<(TotalArray<N>, T)>,
+}
+
+/// A uniform grid of cubic cells for neighbour queries over points with `N` coordinates.
+///
+/// A point is in the cell found by flooring each coordinate divided by the cell size, so `-0.0`
+/// and `0.0` share a cell. Points with a NaN or infinite coordinate have no distance to anything,
+/// so they are kept in a separate bucket: [`get`](SpatialHashGrid::get),
+/// [`remove`](SpatialHashGrid::remove) and [`non_finite`](SpatialHashGrid::non_finite) see them,
+/// but radius and nearest neighbour queries never return them.
+///
+/// ```rust
+/// use total_float_wrap::SpatialHashGrid;
+///
+/// let mut grid = SpatialHashGrid::new(1.0);
+/// let origin = grid.insert([0.0, 0.0], "origin");
+/// grid.insert([0.5, -0.0], "near");
+/// grid.insert([3.0, 4.0], "far");
+/// grid.insert([f64::NAN, 1.0], "lost");
+///
+/// let near: Vec<&str> = grid.radius([0.1, 0.0], 1.0).iter().map(|n| *n.2).collect();
+/// assert_eq!(near, ["origin", "near"]);
+///
+/// let nearest: Vec<&str> = grid.knn([2.0, 2.0], 2).iter().map(|n| *n.2).collect();
+/// assert_eq!(nearest, ["far", "near"]);
+///
+/// assert_eq!(grid.remove(origin), Some(([0.0, 0.0], "origin")));
+/// assert_eq!(grid.non_finite().count(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpatialHashGrid<const N: usize, T> {
+    cell_size: f64,
+    cells: HashMap<[i64; N], Vec<u32>, FloatBuildHasher>,
+    non_finite: Vec<u32>,
+    slots: Vec<Slot<N, T>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+/// A point found by a query: its handle, coordinates and value
+pub type Neighbour<'a, const N: usize, T> = (GridHandle, [f64; N], &'a T);
+
+impl<const N: usize, T> SpatialHashGrid<N, T> {
+    /// Creates an empty grid with the given cell size.
+    ///
+    /// Queries are fastest when the cell size is close to the typical query radius.
+    ///
+    /// Panics if `cell_size` is not positive and finite.
+    pub fn new(cell_size: f64) -> Self {
+        assert!(
+            cell_size > 0.0 && cell_size.is_finite(),
+            "invalid cell size {cell_size}"
+        );
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            non_finite: Vec::new(),
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the side length of the cells
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Returns the number of points in the grid, including non-finite ones
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the grid contains no points
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a point, returning a handle which can be used to remove it.
+    ///
+    /// Panics if more than `u32::MAX` points are stored at once.
+    pub fn insert(&mut self, point: [f64; N], value: T) -> GridHandle {
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                let slot = u32::try_from(self.slots.len()).expect("more than 2^32 points");
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: None,
+                });
+                slot
+            }
+        };
+
+        match self.cell_of(&point) {
+            Some(cell) => self.cells.entry(cell).or_default().push(slot),
+            None => self.non_finite.push(slot),
+        }
+
+        let entry = &mut self.slots[slot as usize];
+        entry.entry = Some((TotalArray(point), value));
+        self.len += 1;
+        GridHandle {
+            slot,
+            generation: entry.generation,
+        }
+    }
+
+    /// Returns the point and value of the handle, if it has not been removed
+    pub fn get(&self, handle: GridHandle) -> Option<([f64; N], &T)> {
+        let slot = self.slots.get(handle.slot as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        let (point, value) = slot.entry.as_ref()?;
+        Some((point.0, value))
+    }
+
+    /// Removes the point of the handle, returning it and its value if it had not been removed
+    pub fn remove(&mut self, handle: GridHandle) -> Option<([f64; N], T)> {
+        let slot = self.slots.get_mut(handle.slot as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        let (TotalArray(point), value) = slot.entry.take()?;
+
+        // a slot whose generation runs out is retired rather than risk a handle being reused
+        if let Some(generation) = slot.generation.checked_add(1) {
+            slot.generation = generation;
+            self.free.push(handle.slot);
+        }
+        self.len -= 1;
+
+        let bucket = match self.cell_of(&point) {
+            Some(cell) => self.cells.get_mut(&cell).expect("point is in its cell"),
+            None => &mut self.non_finite,
+        };
+        let position = bucket.iter().position(|&s| s == handle.slot);
+        bucket.swap_remove(position.expect("point is in its cell"));
+        if bucket.is_empty() {
+            if let Some(cell) = self.cell_of(&point) {
+                self.cells.remove(&cell);
+            }
+        }
+
+        Some((point, value))
+    }
+
+    /// Iterates over every point in the grid, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = Neighbour<'_, N, T>> {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            let (point, value) = slot.entry.as_ref()?;
+            Some((self.handle(i as u32), point.0, value))
+        })
+    }
+
+    /// Iterates over the points with a NaN or infinite coordinate
+    pub fn non_finite(&self) -> impl Iterator<Item = Neighbour<'_, N, T>> {
+        self.non_finite.iter().map(|&slot| self.neighbour(slot))
+    }
+
+    /// Returns the finite points within `radius` of `center`, nearest first.
+    ///
+    /// Points at equal distances are ordered by their handles. Returns nothing if `center` is not
+    /// finite, or `radius` is negative or NaN.
+    pub fn radius(&self, center: [f64; N], radius: f64) -> Vec<Neighbour<'_, N, T>> {
+        let Some(home) = self.cell_of(&center) else {
+            return Vec::new();
+        };
+        if radius.is_nan() || radius < 0.0 {
+            return Vec::new();
+        }
+
+        let mut found = Vec::new();
+        let reach = (radius / self.cell_size).ceil();
+        let cube = (2.0 * reach + 1.0).powi(N as i32);
+        if cube > self.cells.len() as f64 {
+            // the query covers more cells than are occupied, so look at every occupied one
+            for slots in self.cells.values() {
+                self.collect(slots, &center, &mut found);
+            }
+        } else {
+            let reach = reach as i64;
+            for d in 0..=reach {
+                self.collect_ring(home, d, &center, &mut found);
+            }
+        }
+
+        found.retain(|&(distance, _)| distance <= TotalF64(radius));
+        self.sorted(found, usize::MAX)
+    }
+
+    /// Returns the `k` finite points nearest to `center`, nearest first.
+    ///
+    /// Points at equal distances are ordered by their handles. Returns nothing if `center` is not
+    /// finite.
+    pub fn knn(&self, center: [f64; N], k: usize) -> Vec<Neighbour<'_, N, T>> {
+        let Some(home) = self.cell_of(&center) else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let finite = self.len - self.non_finite.len();
+        let mut found = Vec::new();
+        for d in 0_i64.. {
+            let cube = (2 * d + 1).checked_pow(N as u32);
+            if cube.is_none_or(|cube| cube as usize > self.cells.len()) {
+                // searching outwards would visit more cells than are occupied, so finish with
+                // every occupied cell
+                found.clear();
+                for slots in self.cells.values() {
+                    self.collect(slots, &center, &mut found);
+                }
+                break;
+            }
+
+            self.collect_ring(home, d, &center, &mut found);
+            if found.len() == finite {
+                break;
+            }
+
+            // every point outside the rings searched so far is further than d cells away
+            if found.len() >= k {
+                let bound = TotalF64(d as f64 * self.cell_size);
+                found.select_nth_unstable_by_key(k - 1, |&(distance, _)| distance);
+                if found[k - 1].0 <= bound {
+                    break;
+                }
+            }
+        }
+
+        self.sorted(found, k)
+    }
+
+    /// Returns the cell containing the point, or `None` if a coordinate is not finite
+    fn cell_of(&self, point: &[f64; N]) -> Option<[i64; N]> {
+        if !point.iter().all(|x| x.is_finite()) {
+            return None;
+        }
+        // `as` saturates, so coordinates far beyond the cells i64 can number share the end cells
+        Some(point.map(|x| (x / self.cell_size).floor() as i64))
+    }
+
+    /// Adds the distances to every point in the cells `d` steps from `home`
+    fn collect_ring(
+        &self,
+        home: [i64; N],
+        d: i64,
+        center: &[f64; N],
+        found: &mut Vec<(TotalF64, u32)>,
+    ) {
+        let side = 2 * d + 1;
+        let count = side.pow(N as u32);
+        for n in 0..count {
+            let mut digits = n;
+            let mut on_ring = N == 0;
+            let mut cell = [0; N];
+            let mut valid = true;
+            for (c, &h) in cell.iter_mut().zip(&home) {
+                let offset = digits % side - d;
+                digits /= side;
+                on_ring |= offset.abs() == d;
+                match h.checked_add(offset) {
+                    Some(x) => *c = x,
+                    None => valid = false,
+                }
+            }
+
+            if on_ring && valid {
+                if let Some(slots) = self.cells.get(&cell) {
+                    self.collect(slots, center, found);
+                }
+            }
+        }
+    }
+
+    /// Adds the distances to the points in the slots
+    fn collect(&self, slots: &[u32], center: &[f64; N], found: &mut Vec<(TotalF64, u32)>) {
+        for &slot in slots {
+            let (point, _) = self.slots[slot as usize]
+                .entry
+                .as_ref()
+                .expect("occupied slot");
+            found.push((TotalF64(distance(&point.0, center)), slot));
+        }
+    }
+
+    /// Sorts the points by distance then handle, keeping the first `k`
+    fn sorted(&self, mut found: Vec<(TotalF64, u32)>, k: usize) -> Vec<Neighbour<'_, N, T>> {
+        found.sort_unstable_by_key(|&(distance, slot)| (distance, self.handle(slot)));
+        found.truncate(k);
+        found
+            .into_iter()
+            .map(|(_, slot)| self.neighbour(slot))
+            .collect()
+    }
+
+    fn handle(&self, slot: u32) -> GridHandle {
+        GridHandle {
+            slot,
+            generation: self.slots[slot as usize].generation,
+        }
+    }
+
+    fn neighbour(&self, slot: u32) -> Neighbour<'_, N, T> {
+        let (point, value) = self.slots[slot as usize]
+            .entry
+            .as_ref()
+            .expect("occupied slot");
+        (self.handle(slot), point.0, value)
+    }
+}
+
+/// Returns the Euclidean distance between the points, rescaling when the squares overflow
+fn distance<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    let squares = |scale: f64| {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| ((x - y) / scale) * ((x - y) / scale))
+            .sum::<f64>()
+            .sqrt()
+    };
+
+    let distance = squares(1.0);
+    if distance.is_finite() {
+        return distance;
+    }
+    let largest = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max);
+    if largest.is_finite() {
+        largest * squares(largest)
+    } else {
+        largest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small deterministic generator, so the tests need no dependencies
+    fn points(n: usize) -> Vec<[f64; 3]> {
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1_u64 << 53) as f64 * 20.0 - 10.0
+        };
+        (0..n).map(|_| [next(), next(), next()]).collect()
+    }
+
+    fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+        a.iter()
+            .zip(&b)
+            .map(|(x, y)| (x - y) * (x - y))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    #[test]
+    fn test_queries_match_brute_force() {
+        let points = points(500);
+        let mut grid = SpatialHashGrid::new(1.5);
+        for (i, &p) in points.iter().enumerate() {
+            grid.insert(p, i);
+        }
+
+        for &center in &points[..20] {
+            for radius in [0.0, 0.7, 2.0, 40.0] {
+                let mut expected: Vec<usize> = (0..points.len())
+                    .filter(|&i| distance(points[i], center) <= radius)
+                    .collect();
+                expected.sort_by_key(|&i| (TotalF64(distance(points[i], center)), i));
+                let found: Vec<usize> = grid.radius(center, radius).iter().map(|n| *n.2).collect();
+                assert_eq!(found, expected);
+            }
+
+            for k in [1, 7, 600] {
+                let mut expected: Vec<usize> = (0..points.len()).collect();
+                expected.sort_by_key(|&i| (TotalF64(distance(points[i], center)), i));
+                expected.truncate(k);
+                let found: Vec<usize> = grid.knn(center, k).iter().map(|n| *n.2).collect();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_saturated_cells_match_brute_force() {
+        // at 1e300 the cell indices saturate, so points far apart share the end cells
+        let mut points: Vec<[f64; 3]> = points(200)
+            .into_iter()
+            .map(|p| p.map(|x| 1e300 + x * 1e287))
+            .collect();
+        points.extend([[0.0; 3], [1e300, 0.0, 1e300], [-1e300; 3], [1e300; 3]]);
+        let mut grid = SpatialHashGrid::new(1.0);
+        for (i, &p) in points.iter().enumerate() {
+            grid.insert(p, i);
+        }
+
+        let by_distance = |center: [f64; 3]| {
+            let mut all: Vec<(f64, usize)> = (0..points.len())
+                .map(|i| (super::distance(&points[i], &center), i))
+                .collect();
+            all.sort_by_key(|&(d, i)| (TotalF64(d), i));
+            all
+        };
+        for &center in points
+            .iter()
+            .take(10)
+            .chain(&[[1e300; 3], [1e300, 0.0, 0.0]])
+        {
+            let all = by_distance(center);
+            for radius in [0.0, 1.0, 1e287, 1e288, 1e301] {
+                let expected: Vec<usize> = all
+                    .iter()
+                    .filter(|&&(d, _)| d <= radius)
+                    .map(|&(_, i)| i)
+                    .collect();
+                let found: Vec<usize> = grid.radius(center, radius).iter().map(|n| *n.2).collect();
+                assert_eq!(found, expected, "{center:?} {radius}");
+            }
+
+            for k in [1, 5, 300] {
+                let expected: Vec<usize> = all.iter().take(k).map(|&(_, i)| i).collect();
+                let found: Vec<usize> = grid.knn(center, k).iter().map(|n| *n.2).collect();
+                assert_eq!(found, expected, "{center:?} {k}");
+            }
+        }
+
+        let found = grid.radius([1e300; 3], 1e288);
+        assert!(found.len() > 1 && found.len() < 200);
+    }
+
+    #[test]
+    fn test_remove_and_handles() {
+        let mut grid = SpatialHashGrid::new(1.0);
+        let a = grid.insert([0.5, 0.5], 'a');
+        let b = grid.insert([-0.0, 0.0], 'b');
+        assert_eq!(grid.len(), 2);
+
+        assert_eq!(grid.remove(a), Some(([0.5, 0.5], 'a')));
+        assert_eq!(grid.remove(a), None);
+        assert_eq!(grid.get(a), None);
+
+        // the slot is reused, but the old handle stays dead
+        let c = grid.insert([0.5, 0.5], 'c');
+        assert_ne!(a, c);
+        assert_eq!(grid.get(a), None);
+        assert_eq!(grid.get(c), Some(([0.5, 0.5], &'c')));
+
+        let found: Vec<char> = grid.radius([0.0, 0.0], 1.0).iter().map(|n| *n.2).collect();
+        assert_eq!(found, ['b', 'c']);
+        assert_eq!(
+            grid.get(b).map(|(p, _)| p[0].to_bits()),
+            Some((-0.0_f64).to_bits())
+        );
+        assert_eq!(grid.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_non_finite_points() {
+        let mut grid = SpatialHashGrid::new(2.0);
+        let nan = grid.insert([f64::NAN, 0.0], 1);
+        grid.insert([f64::INFINITY, 0.0], 2);
+        grid.insert([1e300, 0.0], 3);
+        grid.insert([0.0, 0.0], 4);
+
+        assert_eq!(grid.len(), 4);
+        assert_eq!(grid.non_finite().count(), 2);
+        let found: Vec<i32> = grid.knn([0.0, 0.0], 10).iter().map(|n| *n.2).collect();
+        assert_eq!(found, [4, 3]);
+        let found: Vec<i32> = grid
+            .radius([0.0, 0.0], f64::INFINITY)
+            .iter()
+            .map(|n| *n.2)
+            .collect();
+        assert_eq!(found, [4, 3]);
+
+        assert!(grid.radius([f64::NAN, 0.0], 1.0).is_empty());
+        assert!(grid.radius([0.0, 0.0], f64::NAN).is_empty());
+        assert!(grid.knn([0.0, f64::NEG_INFINITY], 1).is_empty());
+
+        assert_eq!(grid.remove(nan).map(|(_, v)| v), Some(1));
+        assert_eq!(grid.non_finite().count(), 1);
+    }
+}
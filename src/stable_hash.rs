@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{TotalF32, TotalF64, TotalFloat};
+
+/// The version of the stable hash algorithm, it is bumped if the hash of any value ever changes.
+pub const STABLE_HASH_VERSION: u32 = 1;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The 64 bit FNV-1a hasher used by [`StableHash`].
+#[derive(Debug, Clone)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    /// Creates a hasher at the FNV-1a offset basis, with nothing written
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    /// Feeds the bytes into the hash
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.state ^= b as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Feeds the little-endian bytes of `v` into the hash
+    pub fn write_u64(&mut self, v: u64) {
+        self.write(&v.to_le_bytes());
+    }
+
+    /// Returns the hash of everything written so far
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Types with a stable, platform independent hash, suitable for persisting or sharing.
+///
+/// Unlike `Hash`, which feeds values into whatever `Hasher` is supplied, the output of
+/// [`StableHash::stable_hash`] is pinned by [`STABLE_HASH_VERSION`] and never depends on the
+/// platform, the process or the order a map happens to iterate in.
+///
+/// ## Version 1
+///
+/// Values are encoded to bytes and hashed with 64 bit FNV-1a
+/// (offset basis `0xcbf29ce484222325`, prime `0x100000001b3`).
+///
+/// Every encoding starts with a one byte type tag, so that values of different types with the
+/// same bytes, such as an empty slice and a zero, hash differently.
+///
+/// - `TotalF64` / `TotalF32` (tag `0x01` / `0x02`): the normalised ordered bits used for `Eq`
+///   and `Ord`, as a little-endian `i64` / `i32`, so equal keys always hash identically.
+/// - `TotalFloat`: the value widened to a `TotalF64`.
+/// - integers (tags `0x10` to `0x14` for `i8` to `i128`, `0x18` to `0x1c` for `u8` to `u128`):
+///   their little-endian bytes. `bool` (tag `0x1f`) as a single byte.
+/// - `str` (tag `0x20`): the length as a little-endian `u64`, followed by the UTF-8 bytes.
+/// - slices, arrays and `Vec`s (tag `0x21`): the length as a little-endian `u64`, followed by
+///   each element.
+/// - tuples (tag `0x22`): the number of elements as a byte, followed by each element in turn.
+/// - maps and sets (tags `0x23` and `0x24`): the length as a little-endian `u64` followed by a
+///   little-endian `u64` sum.
+///   Each entry (key then value, for maps) is hashed on its own, the digest is passed through the
+///   SplitMix64 finaliser and the results are summed with wrapping addition. The sum does not
+///   depend on iteration order, so a `HashMap` and `BTreeMap` with the same entries hash equal.
+pub trait StableHash {
+    /// Feeds the encoding of the value into `hasher`.
+    fn stable_hash_into(&self, hasher: &mut StableHasher);
+
+    /// Returns the stable hash of the value.
+    fn stable_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.stable_hash_into(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl StableHash for TotalF64 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write(&[0x01]);
+        hasher.write(&self.normalise().to_le_bytes());
+    }
+}
+
+impl StableHash for TotalF32 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write(&[0x02]);
+        hasher.write(&self.normalise().to_le_bytes());
+    }
+}
+
+impl StableHash for TotalFloat {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        self.widen().stable_hash_into(hasher);
+    }
+}
+
+macro_rules! impl_stable_hash_int {
+    ($($int:ty => $tag:literal),*) => {$(
+        impl StableHash for $int {
+            fn stable_hash_into(&self, hasher: &mut StableHasher) {
+                hasher.write(&[$tag]);
+                hasher.write(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+impl_stable_hash_int!(
+    i8 => 0x10, i16 => 0x11, i32 => 0x12, i64 => 0x13, i128 => 0x14,
+    u8 => 0x18, u16 => 0x19, u32 => 0x1a, u64 => 0x1b, u128 => 0x1c
+);
+
+impl StableHash for bool {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write(&[0x1f, *self as u8]);
+    }
+}
+
+impl StableHash for str {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write(&[0x20]);
+        hasher.write_u64(self.len() as u64);
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl StableHash for String {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        self.as_str().stable_hash_into(hasher);
+    }
+}
+
+impl<T: StableHash + ?Sized> StableHash for &T {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        (**self).stable_hash_into(hasher);
+    }
+}
+
+impl<T: StableHash> StableHash for [T] {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write(&[0x21]);
+        hasher.write_u64(self.len() as u64);
+        for v in self {
+            v.stable_hash_into(hasher);
+        }
+    }
+}
+
+impl<T: StableHash, const N: usize> StableHash for [T; N] {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        self.as_slice().stable_hash_into(hasher);
+    }
+}
+
+impl<T: StableHash> StableHash for Vec<T> {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        self.as_slice().stable_hash_into(hasher);
+    }
+}
+
+impl<A: StableHash, B: StableHash> StableHash for (A, B) {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write(&[0x22, 2]);
+        self.0.stable_hash_into(hasher);
+        self.1.stable_hash_into(hasher);
+    }
+}
+
+impl<A: StableHash, B: StableHash, C: StableHash> StableHash for (A, B, C) {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write(&[0x22, 3]);
+        self.0.stable_hash_into(hasher);
+        self.1.stable_hash_into(hasher);
+        self.2.stable_hash_into(hasher);
+    }
+}
+
+/// The SplitMix64 finaliser, spreads each entry digest before they are summed
+fn mix(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Hashes a collection without depending on the order it is iterated in
+fn stable_hash_unordered<T: StableHash>(
+    hasher: &mut StableHasher,
+    tag: u8,
+    len: usize,
+    entries: impl Iterator<Item = T>,
+) {
+    let sum = entries.fold(0_u64, |sum, entry| {
+        sum.wrapping_add(mix(entry.stable_hash()))
+    });
+
+    hasher.write(&[tag]);
+    hasher.write_u64(len as u64);
+    hasher.write_u64(sum);
+}
+
+impl<K: StableHash, V: StableHash> StableHash for BTreeMap<K, V> {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        stable_hash_unordered(hasher, 0x23, self.len(), self.iter());
+    }
+}
+
+impl<K: StableHash, V: StableHash, S> StableHash for HashMap<K, V, S> {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        stable_hash_unordered(hasher, 0x23, self.len(), self.iter());
+    }
+}
+
+impl<T: StableHash> StableHash for BTreeSet<T> {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        stable_hash_unordered(hasher, 0x24, self.len(), self.iter());
+    }
+}
+
+impl<T: StableHash, S> StableHash for HashSet<T, S> {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        stable_hash_unordered(hasher, 0x24, self.len(), self.iter());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // These vectors pin version 1 of the algorithm, they must never change.
+    // If one does, the version must be bumped and a new set of vectors added alongside.
+
+    #[test]
+    fn test_stable_hash_v1_values() {
+        assert_eq!(STABLE_HASH_VERSION, 1);
+
+        let cases = [
+            (TotalF64(0.0), 0x529a2cdc8ff533ac),
+            (TotalF64(-0.0), 0x685cd83ad34b3424),
+            (TotalF64(1.0), 0x50b0a3dc8e5526f1),
+            (TotalF64(-1.5), 0x6ba8cf3ad6193921),
+            (TotalF64(f64::INFINITY), 0x50b063dc8e54ba31),
+            (TotalF64(f64::NEG_INFINITY), 0x6b8c5f3ad6000019),
+            (
+                TotalF64(f64::from_bits(0x7ff8_0000_0000_0000)),
+                0x509563dc8e3df279,
+            ),
+        ];
+        for (f, expected) in cases {
+            assert_eq!(f.stable_hash(), expected, "{f:?}");
+        }
+
+        assert_eq!(TotalF32(0.0).stable_hash(), 0xfe1a162e1fcabe15);
+        assert_eq!(TotalF32(1.0).stable_hash(), 0xffcd272e213c6f78);
+        assert_eq!(TotalF32(-1.5).stable_hash(), 0xcab4bd01e0b5bedc);
+        assert_eq!(TotalFloat::from(1.0_f32).stable_hash(), 0x50b0a3dc8e5526f1);
+    }
+
+    #[test]
+    fn test_stable_hash_v1_slices() {
+        let empty: [TotalF64; 0] = [];
+        assert_eq!(empty.stable_hash(), 0xe54f8129fc96714c);
+        assert_ne!(empty.stable_hash(), TotalF64(0.0).stable_hash());
+        assert_ne!(0_u32.stable_hash(), 0_i32.stable_hash());
+
+        let values = [TotalF64(1.0), TotalF64(2.0), TotalF64(-0.0)];
+        assert_eq!(values.stable_hash(), 0x9e5f2d5c7ea698ed);
+        assert_eq!(values.to_vec().stable_hash(), 0x9e5f2d5c7ea698ed);
+        assert_eq!(values[..].stable_hash(), 0x9e5f2d5c7ea698ed);
+    }
+
+    #[test]
+    fn test_stable_hash_v1_maps() {
+        let empty: BTreeMap<TotalF64, u32> = BTreeMap::new();
+        assert_eq!(empty.stable_hash(), 0xc1e911eaa3c8ffb2);
+
+        let entries = [(TotalF64(1.0), 10_u32), (TotalF64(-2.5), 20)];
+        let btree: BTreeMap<_, _> = entries.into_iter().collect();
+        let hash: HashMap<_, _> = entries.into_iter().rev().collect();
+        assert_eq!(btree.stable_hash(), 0x229001fa7fd4172d);
+        assert_eq!(hash.stable_hash(), 0x229001fa7fd4172d);
+
+        let other: BTreeMap<_, _> = [(TotalF64(1.0), 20_u32), (TotalF64(-2.5), 10)].into();
+        assert_ne!(other.stable_hash(), btree.stable_hash());
+    }
+}
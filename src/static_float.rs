@@ -0,0 +1,289 @@
+use crate::TotalF64;
+
+/// The number of keys sharing a cache line
+const KEYS_PER_LINE: usize = 64 / core::mem::size_of::<i64>();
+
+/// Normalised keys in Eytzinger (breadth first) order.
+///
+/// Slot `k` holds the root of a subtree whose children are at `2k` and `2k + 1`, so the first
+/// levels of every search share a handful of cache lines, and the descendants a few levels down
+/// are contiguous and can be prefetched. Slot 0 is unused so the arithmetic stays simple.
+#[derive(Debug, Clone)]
+struct Eytzinger {
+    keys: Vec<i64>,
+}
+
+impl Eytzinger {
+    /// Lays out strictly increasing keys, returning the slot of each key alongside the layout
+    fn new(sorted: &[i64]) -> (Self, Vec<usize>) {
+        let mut keys = vec![0; sorted.len() + 1];
+        let mut slots = vec![0; sorted.len()];
+        Self::fill(sorted, &mut keys, &mut slots, &mut 0, 1);
+        (Self { keys }, slots)
+    }
+
+    /// Fills the subtree rooted at `k` with an in order traversal
+    fn fill(sorted: &[i64], keys: &mut [i64], slots: &mut [usize], next: &mut usize, k: usize) {
+        if k < keys.len() {
+            Self::fill(sorted, keys, slots, next, 2 * k);
+            keys[k] = sorted[*next];
+            slots[*next] = k;
+            *next += 1;
+            Self::fill(sorted, keys, slots, next, 2 * k + 1);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len() - 1
+    }
+
+    /// Returns the slot of the least key not less than `key`, or greater than it when `strict`,
+    /// or 0 when there is none
+    fn search(&self, key: i64, strict: bool) -> usize {
+        let mut k = 1;
+        while k < self.keys.len() {
+            prefetch(&self.keys, k * KEYS_PER_LINE);
+            let right = if strict {
+                self.keys[k] <= key
+            } else {
+                self.keys[k] < key
+            };
+            k = 2 * k + right as usize;
+        }
+
+        // each step right sets a trailing bit, so undo those and the last step left to find
+        // the last key where the search turned left
+        k >> (k.trailing_ones() + 1)
+    }
+
+    fn find(&self, key: i64) -> Option<usize> {
+        let k = self.search(key, false);
+        (k != 0 && self.keys[k] == key).then_some(k)
+    }
+
+    fn key(&self, k: usize) -> Option<TotalF64> {
+        (k != 0).then(|| TotalF64::from_normalised(self.keys[k]))
+    }
+}
+
+/// Hints that `keys[i]` is about to be read, when `i` is within the layout
+#[inline(always)]
+fn prefetch(keys: &[i64], i: usize) {
+    #[cfg(target_arch = "x86_64")]
+    if i < keys.len() {
+        // SAFETY: prefetching is only a hint and never faults, and the pointer is in bounds anyway
+        unsafe {
+            use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+            _mm_prefetch::<_MM_HINT_T0>(keys.as_ptr().add(i) as *const i8);
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = (keys, i);
+}
+
+/// An immutable set of `TotalF64`, laid out for fast searching.
+///
+/// The keys are stored normalised in an Eytzinger layout, which makes searches far friendlier to
+/// the cache than a binary search over a sorted vector once the set outgrows it.
+/// All lookups follow the total order, as for `TotalF64`.
+///
+/// ```rust
+/// use total_float_wrap::{StaticFloatSet, TotalF64};
+///
+/// let set: StaticFloatSet = [0.5, 1.0, 2.0, 4.0].into_iter().map(TotalF64).collect();
+///
+/// assert!(set.contains(TotalF64(2.0)));
+/// assert_eq!(set.lower_bound(TotalF64(1.0)), Some(TotalF64(1.0)));
+/// assert_eq!(set.upper_bound(TotalF64(1.0)), Some(TotalF64(2.0)));
+/// assert_eq!(set.upper_bound(TotalF64(4.0)), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticFloatSet {
+    layout: Eytzinger,
+}
+
+impl StaticFloatSet {
+    /// Returns the number of values in the set
+    pub fn len(&self) -> usize {
+        self.layout.len()
+    }
+
+    /// Returns whether the set contains no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the set contains the value
+    pub fn contains(&self, value: TotalF64) -> bool {
+        self.layout.find(value.normalise()).is_some()
+    }
+
+    /// Returns the least value in the set which is greater than or equal to `value`
+    pub fn lower_bound(&self, value: TotalF64) -> Option<TotalF64> {
+        self.layout
+            .key(self.layout.search(value.normalise(), false))
+    }
+
+    /// Returns the least value in the set which is strictly greater than `value`
+    pub fn upper_bound(&self, value: TotalF64) -> Option<TotalF64> {
+        self.layout.key(self.layout.search(value.normalise(), true))
+    }
+}
+
+impl FromIterator<TotalF64> for StaticFloatSet {
+    fn from_iter<I: IntoIterator<Item = TotalF64>>(iter: I) -> Self {
+        let mut keys: Vec<i64> = iter.into_iter().map(|v| v.normalise()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        Self {
+            layout: Eytzinger::new(&keys).0,
+        }
+    }
+}
+
+/// An immutable map keyed by `TotalF64`, laid out for fast searching, see [`StaticFloatSet`].
+///
+/// When built from an iterator with repeated keys, the last value for each key is kept.
+#[derive(Debug, Clone)]
+pub struct StaticFloatMap<V> {
+    layout: Eytzinger,
+    /// The values in the same order as the keys, offset by one as slot 0 is unused
+    values: Vec<V>,
+}
+
+impl<V> StaticFloatMap<V> {
+    /// Returns the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.layout.len()
+    }
+
+    /// Returns whether the map contains no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the map contains the key
+    pub fn contains(&self, key: TotalF64) -> bool {
+        self.layout.find(key.normalise()).is_some()
+    }
+
+    /// Returns the value stored under the key
+    pub fn get(&self, key: TotalF64) -> Option<&V> {
+        let k = self.layout.find(key.normalise())?;
+        Some(&self.values[k - 1])
+    }
+
+    /// Returns the entry with the least key which is greater than or equal to `key`
+    pub fn lower_bound(&self, key: TotalF64) -> Option<(TotalF64, &V)> {
+        self.entry(self.layout.search(key.normalise(), false))
+    }
+
+    /// Returns the entry with the least key which is strictly greater than `key`
+    pub fn upper_bound(&self, key: TotalF64) -> Option<(TotalF64, &V)> {
+        self.entry(self.layout.search(key.normalise(), true))
+    }
+
+    fn entry(&self, k: usize) -> Option<(TotalF64, &V)> {
+        Some((self.layout.key(k)?, &self.values[k - 1]))
+    }
+}
+
+impl<V> FromIterator<(TotalF64, V)> for StaticFloatMap<V> {
+    fn from_iter<I: IntoIterator<Item = (TotalF64, V)>>(iter: I) -> Self {
+        let mut entries: Vec<(i64, V)> =
+            iter.into_iter().map(|(k, v)| (k.normalise(), v)).collect();
+
+        // the sort is stable, so reversing first leaves the last value of each key first
+        entries.reverse();
+        entries.sort_by_key(|&(k, _)| k);
+        entries.dedup_by_key(|&mut (k, _)| k);
+
+        let keys: Vec<i64> = entries.iter().map(|&(k, _)| k).collect();
+        let (layout, slots) = Eytzinger::new(&keys);
+
+        let mut values: Vec<(usize, V)> = slots
+            .into_iter()
+            .zip(entries)
+            .map(|(k, (_, v))| (k, v))
+            .collect();
+        values.sort_unstable_by_key(|&(k, _)| k);
+
+        Self {
+            layout,
+            values: values.into_iter().map(|(_, v)| v).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SortedTotalVec;
+    use core::ops::Bound;
+
+    fn values() -> Vec<TotalF64> {
+        [
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            -3.0,
+            -1.0,
+            -0.0,
+            0.0,
+            0.25,
+            1.0,
+            2.0,
+            7.5,
+            f64::INFINITY,
+            f64::NAN,
+        ]
+        .map(TotalF64)
+        .into()
+    }
+
+    #[test]
+    fn test_static_float_set_matches_sorted() {
+        // every size up to a few full levels, so partially filled last levels are covered
+        let all = values();
+        for n in 0..=all.len() {
+            let set: StaticFloatSet = all[..n].iter().copied().collect();
+            let sorted: SortedTotalVec = all[..n].iter().copied().collect();
+            assert_eq!(set.len(), n);
+
+            for &q in &all {
+                let lower = sorted.select(sorted.rank(q));
+                let upper = sorted.range((Bound::Excluded(q), Bound::Unbounded)).next();
+
+                assert_eq!(set.contains(q), sorted.contains(q), "{n} {q:?}");
+                assert_eq!(set.lower_bound(q), lower, "{n} {q:?}");
+                assert_eq!(set.upper_bound(q), upper, "{n} {q:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_static_float_set_between_keys() {
+        let set: StaticFloatSet = (0..1000).map(|i| TotalF64(i as f64)).collect();
+        assert_eq!(set.lower_bound(TotalF64(499.5)), Some(TotalF64(500.0)));
+        assert_eq!(set.upper_bound(TotalF64(-0.0)), Some(TotalF64(0.0)));
+        assert_eq!(set.lower_bound(TotalF64(999.5)), None);
+        assert!(!set.contains(TotalF64(-0.0)));
+        assert!(StaticFloatSet::from_iter([]).is_empty());
+    }
+
+    #[test]
+    fn test_static_float_map() {
+        let map: StaticFloatMap<&str> = [(2.0, "a"), (1.0, "b"), (2.0, "c"), (-0.0, "d")]
+            .into_iter()
+            .map(|(k, v)| (TotalF64(k), v))
+            .collect();
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(TotalF64(2.0)), Some(&"c"));
+        assert_eq!(map.get(TotalF64(0.0)), None);
+        assert!(map.contains(TotalF64(-0.0)));
+        assert_eq!(map.lower_bound(TotalF64(0.0)), Some((TotalF64(1.0), &"b")));
+        assert_eq!(map.upper_bound(TotalF64(1.0)), Some((TotalF64(2.0), &"c")));
+        assert_eq!(map.upper_bound(TotalF64(2.0)), None);
+    }
+}
@@ -0,0 +1,259 @@
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use crate::TotalF64;
+
+/// An array of floats implementing `Eq`, `Ord` and `Hash` element-wise under the total order,
+/// so that coordinates can key maps and sets.
+///
+/// Arrays are ordered lexicographically, comparing each element as a [`TotalF64`].
+///
+/// ```rust
+/// use std::collections::BTreeSet;
+/// use total_float_wrap::{TotalPoint2, TotalPoint3};
+///
+/// let points: BTreeSet<TotalPoint2> = [(1.0, 2.0), (0.0, 5.0), (1.0, -0.0), (1.0, 0.0)]
+///     .into_iter()
+///     .map(TotalPoint2::from)
+///     .collect();
+/// let sorted: Vec<(f64, f64)> = points.into_iter().map(Into::into).collect();
+/// assert_eq!(sorted, [(0.0, 5.0), (1.0, -0.0), (1.0, 0.0), (1.0, 2.0)]);
+///
+/// assert_ne!(TotalPoint3::from([0.0, f64::NAN, 1.0]), TotalPoint3::from([0.0, -f64::NAN, 1.0]));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct TotalArray<const N: usize>(pub [f64; N]);
+
+/// A point with `N` coordinates, compared by the total order
+pub type TotalPoint<const N: usize> = TotalArray<N>;
+
+/// A 2D point, compared by the total order
+pub type TotalPoint2 = TotalArray<2>;
+
+/// A 3D point, compared by the total order
+pub type TotalPoint3 = TotalArray<3>;
+
+impl<const N: usize> TotalArray<N> {
+    /// Returns the elements wrapped as `TotalF64`
+    pub fn to_total(self) -> [TotalF64; N] {
+        self.0.map(TotalF64)
+    }
+}
+
+impl<const N: usize> Default for TotalArray<N> {
+    fn default() -> Self {
+        TotalArray([0.0; N])
+    }
+}
+
+impl<const N: usize> PartialEq for TotalArray<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_total() == other.to_total()
+    }
+}
+
+impl<const N: usize> Eq for TotalArray<N> {}
+
+impl<const N: usize> PartialOrd for TotalArray<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for TotalArray<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_total().cmp(&other.to_total())
+    }
+}
+
+impl<const N: usize> Hash for TotalArray<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // hashes exactly what is compared, as TotalF64 does for a single element
+        self.to_total().hash(state);
+    }
+}
+
+impl<const N: usize> From<[f64; N]> for TotalArray<N> {
+    fn from(array: [f64; N]) -> Self {
+        TotalArray(array)
+    }
+}
+
+impl<const N: usize> From<TotalArray<N>> for [f64; N] {
+    fn from(TotalArray(array): TotalArray<N>) -> Self {
+        array
+    }
+}
+
+impl<const N: usize> From<[TotalF64; N]> for TotalArray<N> {
+    fn from(array: [TotalF64; N]) -> Self {
+        TotalArray(array.map(f64::from))
+    }
+}
+
+impl<const N: usize> From<TotalArray<N>> for [TotalF64; N] {
+    fn from(array: TotalArray<N>) -> Self {
+        array.to_total()
+    }
+}
+
+impl From<(f64, f64)> for TotalArray<2> {
+    fn from((x, y): (f64, f64)) -> Self {
+        TotalArray([x, y])
+    }
+}
+
+impl From<TotalArray<2>> for (f64, f64) {
+    fn from(TotalArray([x, y]): TotalArray<2>) -> Self {
+        (x, y)
+    }
+}
+
+impl From<(f64, f64, f64)> for TotalArray<3> {
+    fn from((x, y, z): (f64, f64, f64)) -> Self {
+        TotalArray([x, y, z])
+    }
+}
+
+impl From<TotalArray<3>> for (f64, f64, f64) {
+    fn from(TotalArray([x, y, z]): TotalArray<3>) -> Self {
+        (x, y, z)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::DVec2> for TotalArray<2> {
+    fn from(v: glam::DVec2) -> Self {
+        TotalArray(v.to_array())
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<TotalArray<2>> for glam::DVec2 {
+    fn from(TotalArray(array): TotalArray<2>) -> Self {
+        glam::DVec2::from_array(array)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::DVec3> for TotalArray<3> {
+    fn from(v: glam::DVec3) -> Self {
+        TotalArray(v.to_array())
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<TotalArray<3>> for glam::DVec3 {
+    fn from(TotalArray(array): TotalArray<3>) -> Self {
+        glam::DVec3::from_array(array)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::DVec4> for TotalArray<4> {
+    fn from(v: glam::DVec4) -> Self {
+        TotalArray(v.to_array())
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<TotalArray<4>> for glam::DVec4 {
+    fn from(TotalArray(array): TotalArray<4>) -> Self {
+        glam::DVec4::from_array(array)
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> From<nalgebra::SVector<f64, N>> for TotalArray<N> {
+    fn from(v: nalgebra::SVector<f64, N>) -> Self {
+        TotalArray(v.into())
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> From<TotalArray<N>> for nalgebra::SVector<f64, N> {
+    fn from(TotalArray(array): TotalArray<N>) -> Self {
+        array.into()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> From<nalgebra::Point<f64, N>> for TotalArray<N> {
+    fn from(p: nalgebra::Point<f64, N>) -> Self {
+        p.coords.into()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> From<TotalArray<N>> for nalgebra::Point<f64, N> {
+    fn from(TotalArray(array): TotalArray<N>) -> Self {
+        array.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash<T: Hash>(value: T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_total_array_order() {
+        let nan = f64::NAN;
+        let mut points = [
+            TotalPoint2::from((nan, 0.0)),
+            TotalPoint2::from((1.0, nan)),
+            TotalPoint2::from((-nan, 5.0)),
+            TotalPoint2::from((1.0, -0.0)),
+            TotalPoint2::from((1.0, 0.0)),
+        ];
+        points.sort();
+
+        let expected = [(-nan, 5.0), (1.0, -0.0), (1.0, 0.0), (1.0, nan), (nan, 0.0)];
+        for (point, (x, y)) in points.iter().zip(expected) {
+            assert_eq!(point.to_total(), [TotalF64(x), TotalF64(y)]);
+        }
+    }
+
+    #[test]
+    fn test_total_array_eq_and_hash() {
+        let a = TotalPoint3::from([0.5, f64::NAN, -0.0]);
+        let b = TotalPoint3::from((0.5, f64::NAN, -0.0));
+        assert_eq!(a, b);
+        assert_eq!(hash(a), hash(b));
+        assert_eq!(
+            hash(a),
+            hash([TotalF64(0.5), TotalF64(f64::NAN), TotalF64(-0.0)])
+        );
+        assert_ne!(a, TotalPoint3::from([0.5, f64::NAN, 0.0]));
+        assert_eq!(TotalArray::<4>::default(), TotalArray([0.0; 4]));
+        assert_eq!(<(f64, f64, f64)>::from(a).0, 0.5);
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn test_total_array_glam() {
+        let v = glam::DVec3::new(1.0, -0.0, 2.5);
+        let point = TotalPoint3::from(v);
+        assert_eq!(point, TotalArray([1.0, -0.0, 2.5]));
+        assert_eq!(glam::DVec3::from(point), v);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn test_total_array_nalgebra() {
+        let v = nalgebra::Vector2::new(1.0, -0.0);
+        let point = TotalPoint2::from(v);
+        assert_eq!(point, TotalArray([1.0, -0.0]));
+        assert_eq!(nalgebra::Vector2::from(point), v);
+        assert_eq!(
+            TotalPoint2::from(nalgebra::Point2::new(3.0, 4.0)),
+            TotalArray([3.0, 4.0])
+        );
+    }
+}
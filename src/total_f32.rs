@@ -1,12 +1,14 @@
 use core::cmp::Ordering;
 use core::hash::{Hash, Hasher};
 
+use crate::TotalF64;
+
 #[derive(Default, Debug, Copy, Clone)]
 pub struct TotalF32(pub f32);
 
 impl TotalF32 {
     /// Normalises the float value to an i32
-    fn normalise(&self) -> i32 {
+    pub(crate) fn normalise(&self) -> i32 {
         let val = self.0.to_bits() as i32;
 
         // copied from https://github.com/rust-lang/rust/pull/72568/files
@@ -35,6 +37,13 @@ impl TotalF32 {
         // On positive values, the mask is all zeros, so it's a no-op.
         val ^ (((val >> 31) as u32) >> 1) as i32
     }
+
+    /// Inverts `normalise`, the mask depends only on the sign bit which normalising preserves
+    pub(crate) fn from_normalised(val: i32) -> Self {
+        TotalF32(f32::from_bits(
+            (val ^ (((val >> 31) as u32) >> 1) as i32) as u32,
+        ))
+    }
 }
 
 impl From<TotalF32> for f32 {
@@ -45,7 +54,7 @@ impl From<TotalF32> for f32 {
 
 impl From<f32> for TotalF32 {
     fn from(f: f32) -> Self {
-        TotalF32(f.into())
+        TotalF32(f)
     }
 }
 
@@ -69,6 +78,20 @@ impl Ord for TotalF32 {
     }
 }
 
+impl PartialEq<TotalF64> for TotalF32 {
+    /// Compares the exact values, as if `self` had been widened to a `TotalF64`
+    fn eq(&self, other: &TotalF64) -> bool {
+        TotalF64::from(*self) == *other
+    }
+}
+
+impl PartialOrd<TotalF64> for TotalF32 {
+    /// Orders the exact values, as if `self` had been widened to a `TotalF64`
+    fn partial_cmp(&self, other: &TotalF64) -> Option<Ordering> {
+        Some(TotalF64::from(*self).cmp(other))
+    }
+}
+
 impl Hash for TotalF32 {
     fn hash<H: Hasher>(&self, state: &mut H) {
         // this value is used for the hash so that we can enforce a constraint from Hash:
@@ -87,7 +110,7 @@ mod tests {
     impl core::ops::Neg for TotalF32 {
         type Output = Self;
 
-        fn neg(self: Self) -> Self {
+        fn neg(self) -> Self {
             let Self(f) = self;
             Self(f.neg())
         }
@@ -108,6 +131,18 @@ mod tests {
         assert_eq!(v, TotalF32(f));
     }
 
+    #[test]
+    fn test_total_f32_cmp_total_f64() {
+        assert_eq!(TotalF32(0.5), TotalF64(0.5));
+        assert_ne!(TotalF32(0.1), TotalF64(0.1));
+        assert_ne!(TotalF32(-0.0), TotalF64(0.0));
+        assert!(TotalF32(0.1) > TotalF64(0.1));
+        assert!(TotalF32(-0.0) < TotalF64(0.0));
+        assert!(TotalF32(f32::INFINITY) < TotalF64(f64::NAN));
+        assert!(TotalF32(-f32::NAN) < TotalF64(f64::NEG_INFINITY));
+        assert_eq!(TotalF32(f32::NAN), TotalF64(f64::NAN));
+    }
+
     #[test]
     /// Adapted from https://github.com/rust-lang/rust/pull/72568/files
     fn test_total_f32_cmp() {
@@ -1,12 +1,22 @@
 use core::cmp::Ordering;
 use core::hash::{Hash, Hasher};
 
+use crate::{InexactError, RoundingMode, TotalF32};
+
+/// The number of low mantissa bits an f64 has over an f32
+const MANTISSA_SHIFT: u32 = f64::MANTISSA_DIGITS - f32::MANTISSA_DIGITS;
+
+const F64_EXPONENT_MASK: u64 = 0x7ff0_0000_0000_0000;
+const F64_MANTISSA_MASK: u64 = 0x000f_ffff_ffff_ffff;
+const F32_EXPONENT_MASK: u32 = 0x7f80_0000;
+const F32_MANTISSA_MASK: u32 = 0x007f_ffff;
+
 #[derive(Default, Debug, Copy, Clone)]
 pub struct TotalF64(pub f64);
 
 impl TotalF64 {
     /// Normalises the float value to an i64
-    fn normalise(&self) -> i64 {
+    pub(crate) fn normalise(&self) -> i64 {
         let val = self.0.to_bits() as i64;
 
         // copied from https://github.com/rust-lang/rust/pull/72568/files
@@ -35,6 +45,75 @@ impl TotalF64 {
         // On positive values, the mask is all zeros, so it's a no-op.
         val ^ (((val >> 63) as u64) >> 1) as i64
     }
+
+    /// Inverts `normalise`, the mask depends only on the sign bit which normalising preserves
+    pub(crate) fn from_normalised(val: i64) -> Self {
+        TotalF64(f64::from_bits(
+            (val ^ (((val >> 63) as u64) >> 1) as i64) as u64,
+        ))
+    }
+
+    /// Narrows the value to a `TotalF32`, rounding according to `mode` when it is not exactly
+    /// representable.
+    ///
+    /// Values beyond the range of f32 round to infinity or `f32::MAX` as the mode dictates.
+    /// NaN payloads keep their high bits and drop the low ones, a NaN whose payload would become
+    /// empty is given the smallest payload instead so it stays a NaN.
+    /// Narrowing is monotone with respect to the total order for every mode.
+    ///
+    /// Only [`RoundingMode::Exact`] can fail, it does so if any information would be lost.
+    pub fn to_f32_rounded(self, mode: RoundingMode) -> Result<TotalF32, InexactError> {
+        let TotalF64(f) = self;
+
+        if f.is_nan() {
+            let bits = f.to_bits();
+            if mode == RoundingMode::Exact && bits & ((1 << MANTISSA_SHIFT) - 1) != 0 {
+                return Err(InexactError);
+            }
+
+            let sign = ((bits >> 63) as u32) << 31;
+            let payload = ((bits & F64_MANTISSA_MASK) >> MANTISSA_SHIFT) as u32;
+            return Ok(TotalF32(f32::from_bits(
+                sign | F32_EXPONENT_MASK | payload.max(1),
+            )));
+        }
+
+        // `as` rounds to nearest, ties to even, and the widened result is exact so it can be
+        // used to work out which side of the true value the nearest candidate fell on
+        let nearest = f as f32;
+        let widened = nearest as f64;
+        if widened == f {
+            return Ok(TotalF32(nearest));
+        }
+
+        let rounded = match mode {
+            RoundingMode::NearestEven => nearest,
+            RoundingMode::TowardPositive if widened < f => nearest.next_up(),
+            RoundingMode::TowardNegative if widened > f => nearest.next_down(),
+            RoundingMode::TowardZero if widened > f && f > 0.0 => nearest.next_down(),
+            RoundingMode::TowardZero if widened < f && f < 0.0 => nearest.next_up(),
+            RoundingMode::Exact => return Err(InexactError),
+            _ => nearest,
+        };
+
+        Ok(TotalF32(rounded))
+    }
+}
+
+impl From<TotalF32> for TotalF64 {
+    /// Widens the value losslessly, this preserves the total order.
+    ///
+    /// Unlike `as`, NaNs are never quietened: the payload is kept in the high bits of the mantissa.
+    fn from(TotalF32(f): TotalF32) -> Self {
+        if f.is_nan() {
+            let bits = f.to_bits();
+            let sign = ((bits >> 31) as u64) << 63;
+            let payload = ((bits & F32_MANTISSA_MASK) as u64) << MANTISSA_SHIFT;
+            TotalF64(f64::from_bits(sign | F64_EXPONENT_MASK | payload))
+        } else {
+            TotalF64(f as f64)
+        }
+    }
 }
 
 impl From<TotalF64> for f64 {
@@ -45,7 +124,14 @@ impl From<TotalF64> for f64 {
 
 impl From<f64> for TotalF64 {
     fn from(f: f64) -> Self {
-        TotalF64(f.into())
+        TotalF64(f)
+    }
+}
+
+impl From<f32> for TotalF64 {
+    /// Widens the value losslessly, see `From<TotalF32>`
+    fn from(f: f32) -> Self {
+        TotalF32(f).into()
     }
 }
 
@@ -69,6 +155,20 @@ impl Ord for TotalF64 {
     }
 }
 
+impl PartialEq<TotalF32> for TotalF64 {
+    /// Compares the exact values, as if `other` had been widened to a `TotalF64`
+    fn eq(&self, other: &TotalF32) -> bool {
+        *self == TotalF64::from(*other)
+    }
+}
+
+impl PartialOrd<TotalF32> for TotalF64 {
+    /// Orders the exact values, as if `other` had been widened to a `TotalF64`
+    fn partial_cmp(&self, other: &TotalF32) -> Option<Ordering> {
+        Some(self.cmp(&TotalF64::from(*other)))
+    }
+}
+
 impl Hash for TotalF64 {
     fn hash<H: Hasher>(&self, state: &mut H) {
         // this value is used for the hash so that we can enforce a constraint from Hash:
@@ -87,7 +187,7 @@ mod tests {
     impl core::ops::Neg for TotalF64 {
         type Output = Self;
 
-        fn neg(self: Self) -> Self {
+        fn neg(self) -> Self {
             let Self(f) = self;
             Self(f.neg())
         }
@@ -108,6 +208,142 @@ mod tests {
         assert_eq!(v, TotalF64(f));
     }
 
+    #[test]
+    fn test_total_f64_from_total_f32() {
+        let values = [
+            f32::NEG_INFINITY,
+            -f32::MAX,
+            -1.5,
+            -f32::MIN_POSITIVE / 4.0,
+            -0.0,
+            0.0,
+            f32::MIN_POSITIVE / 4.0,
+            1.5,
+            f32::MAX,
+            f32::INFINITY,
+        ];
+
+        for w in values.windows(2) {
+            let (a, b) = (TotalF32(w[0]), TotalF32(w[1]));
+            assert!(TotalF64::from(a) < TotalF64::from(b));
+            assert_eq!(TotalF64::from(a).0, w[0] as f64);
+        }
+
+        let s_nan = f32::from_bits(0x7f80_0001);
+        let widened = TotalF64::from(TotalF32(s_nan));
+        assert_eq!(widened.0.to_bits(), 0x7ff0_0000_2000_0000);
+        assert_eq!(
+            Ordering::Less,
+            widened.cmp(&TotalF64::from(TotalF32(f32::NAN)))
+        );
+        assert_eq!(
+            Ordering::Less,
+            TotalF64::from(TotalF32(-s_nan)).cmp(&TotalF64(f64::MIN))
+        );
+    }
+
+    #[test]
+    fn test_total_f64_to_f32_rounded() {
+        let third = TotalF64(1.0 / 3.0);
+        let down = third.to_f32_rounded(RoundingMode::TowardNegative).unwrap();
+        let up = third.to_f32_rounded(RoundingMode::TowardPositive).unwrap();
+        assert_eq!(down.0.next_up(), up.0);
+        assert!((down.0 as f64) < third.0 && third.0 < (up.0 as f64));
+        assert_eq!(third.to_f32_rounded(RoundingMode::TowardZero), Ok(down));
+        assert_eq!(
+            third.to_f32_rounded(RoundingMode::NearestEven),
+            Ok(TotalF32(1.0 / 3.0))
+        );
+        assert_eq!(third.to_f32_rounded(RoundingMode::Exact), Err(InexactError));
+
+        let neg = -third;
+        assert_eq!(
+            neg.to_f32_rounded(RoundingMode::TowardZero),
+            Ok(TotalF32(-down.0))
+        );
+        assert_eq!(
+            neg.to_f32_rounded(RoundingMode::TowardPositive),
+            Ok(TotalF32(-down.0))
+        );
+        assert_eq!(
+            neg.to_f32_rounded(RoundingMode::TowardNegative),
+            Ok(TotalF32(-up.0))
+        );
+
+        assert_eq!(
+            TotalF64(0.5).to_f32_rounded(RoundingMode::Exact),
+            Ok(TotalF32(0.5))
+        );
+        assert_eq!(
+            TotalF64(-0.0).to_f32_rounded(RoundingMode::Exact),
+            Ok(TotalF32(-0.0))
+        );
+
+        let huge = TotalF64(f64::MAX);
+        assert_eq!(
+            huge.to_f32_rounded(RoundingMode::NearestEven),
+            Ok(TotalF32(f32::INFINITY))
+        );
+        assert_eq!(
+            huge.to_f32_rounded(RoundingMode::TowardZero),
+            Ok(TotalF32(f32::MAX))
+        );
+        assert_eq!(
+            (-huge).to_f32_rounded(RoundingMode::TowardPositive),
+            Ok(TotalF32(f32::MIN))
+        );
+
+        let tiny = TotalF64(f64::MIN_POSITIVE);
+        assert_eq!(
+            tiny.to_f32_rounded(RoundingMode::NearestEven),
+            Ok(TotalF32(0.0))
+        );
+        assert_eq!(
+            tiny.to_f32_rounded(RoundingMode::TowardPositive),
+            Ok(TotalF32(f32::from_bits(1)))
+        );
+        assert_eq!(
+            (-tiny).to_f32_rounded(RoundingMode::TowardZero),
+            Ok(TotalF32(-0.0))
+        );
+    }
+
+    #[test]
+    fn test_total_f64_to_f32_rounded_nan() {
+        let q_nan = TotalF64(f64::from_bits(0x7ff8_0000_2000_0000));
+        assert_eq!(
+            q_nan
+                .to_f32_rounded(RoundingMode::Exact)
+                .unwrap()
+                .0
+                .to_bits(),
+            0x7fc0_0001
+        );
+
+        // payload only in the dropped bits, this must stay a (signaling) NaN
+        let s_nan = TotalF64(f64::from_bits(0xfff0_0000_0000_0001));
+        assert_eq!(s_nan.to_f32_rounded(RoundingMode::Exact), Err(InexactError));
+        assert_eq!(
+            s_nan
+                .to_f32_rounded(RoundingMode::NearestEven)
+                .unwrap()
+                .0
+                .to_bits(),
+            0xff80_0001
+        );
+
+        for bits in [
+            0x7ff0_0000_0000_0001,
+            0x7ff4_0000_0000_0000,
+            0x7fff_ffff_ffff_ffff_u64,
+        ] {
+            let nan = TotalF64(f64::from_bits(bits));
+            let narrowed = nan.to_f32_rounded(RoundingMode::NearestEven).unwrap();
+            assert!(narrowed.0.is_nan());
+            assert!(narrowed > TotalF32(f32::INFINITY));
+        }
+    }
+
     #[test]
     /// Adapted from https://github.com/rust-lang/rust/pull/72568/files
     fn test_total_f64_cmp() {
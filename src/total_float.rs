@@ -0,0 +1,147 @@
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use crate::{TotalF32, TotalF64};
+
+/// A float of either width, compared and hashed by its exact value.
+///
+/// This allows keys of both widths to live in the same map: `TotalFloat::F32(x)` is equal to, and
+/// hashes identically to, `TotalFloat::F64(TotalF64::from(x))`. The widening is lossless, unlike
+/// `x as f64`, which quietens signalling NaNs.
+/// Ordering agrees with the total order of the value widened to a `TotalF64`, which keeps NaN
+/// payloads in the high bits of the mantissa.
+///
+/// The hash is the same as that of the widened `TotalF64`.
+#[derive(Debug, Copy, Clone)]
+pub enum TotalFloat {
+    F32(TotalF32),
+    F64(TotalF64),
+}
+
+impl TotalFloat {
+    /// Widens the value to a `TotalF64`, this is lossless
+    pub fn widen(self) -> TotalF64 {
+        match self {
+            TotalFloat::F32(f) => f.into(),
+            TotalFloat::F64(f) => f,
+        }
+    }
+}
+
+impl Default for TotalFloat {
+    fn default() -> Self {
+        TotalFloat::F64(TotalF64::default())
+    }
+}
+
+impl From<TotalF32> for TotalFloat {
+    fn from(f: TotalF32) -> Self {
+        TotalFloat::F32(f)
+    }
+}
+
+impl From<TotalF64> for TotalFloat {
+    fn from(f: TotalF64) -> Self {
+        TotalFloat::F64(f)
+    }
+}
+
+impl From<f32> for TotalFloat {
+    fn from(f: f32) -> Self {
+        TotalFloat::F32(TotalF32(f))
+    }
+}
+
+impl From<f64> for TotalFloat {
+    fn from(f: f64) -> Self {
+        TotalFloat::F64(TotalF64(f))
+    }
+}
+
+impl From<TotalFloat> for TotalF64 {
+    fn from(f: TotalFloat) -> Self {
+        f.widen()
+    }
+}
+
+impl PartialEq for TotalFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.widen() == other.widen()
+    }
+}
+
+impl Eq for TotalFloat {}
+
+impl PartialOrd for TotalFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TotalFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.widen().cmp(&other.widen())
+    }
+}
+
+impl Hash for TotalFloat {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // equality is defined on the widened value, so hashing it keeps k1 == k2 -> hash(k1) == hash(k2)
+        self.widen().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+
+    fn hash_of(v: impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        v.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_total_float_eq_across_widths() {
+        for f in [0.0, -0.0, 0.5, -1.75, f32::MAX, f32::NEG_INFINITY, f32::NAN] {
+            let narrow = TotalFloat::from(f);
+            let wide = TotalFloat::from(f as f64);
+            assert_eq!(narrow, wide);
+            assert_eq!(hash_of(narrow), hash_of(wide));
+            assert_eq!(hash_of(narrow), hash_of(TotalF64(f as f64)));
+        }
+
+        assert_ne!(TotalFloat::from(0.1_f32), TotalFloat::from(0.1_f64));
+        assert_ne!(TotalFloat::from(-0.0_f32), TotalFloat::from(0.0_f64));
+    }
+
+    #[test]
+    fn test_total_float_cmp() {
+        assert_eq!(
+            Ordering::Greater,
+            TotalFloat::from(0.1_f32).cmp(&TotalFloat::from(0.1_f64))
+        );
+        assert_eq!(
+            Ordering::Less,
+            TotalFloat::from(-0.0_f64).cmp(&TotalFloat::from(0.0_f32))
+        );
+        assert_eq!(
+            Ordering::Less,
+            TotalFloat::from(f32::INFINITY).cmp(&TotalFloat::from(f64::NAN))
+        );
+    }
+
+    #[test]
+    fn test_total_float_map_key() {
+        let mut map: HashMap<TotalFloat, u32> = HashMap::new();
+        map.insert(1.5_f32.into(), 1);
+        map.insert(1.5_f64.into(), 2);
+        map.insert(0.1_f64.into(), 3);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1.5_f32.into()), Some(&2));
+        assert_eq!(map.get(&0.1_f32.into()), None);
+    }
+}
@@ -0,0 +1,206 @@
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use crate::{TotalF32, TotalF64};
+
+/// A dynamically typed number, ordered by exact mathematical value across all variants.
+///
+/// Ordering agrees with the total order of each variant: floats of both widths are compared
+/// as if widened to a `TotalF64`, and floats are compared against integers exactly with
+/// `cmp_int`, which places NaNs below or above every integer according to their sign.
+///
+/// When two values are mathematically equal the variant breaks the tie, `Int < F32 < F64`.
+/// A float zero is equal in value to `Int(0)`, but between floats `-0.0` is still less than `0.0`.
+/// This gives, for example:
+///
+/// ```rust
+/// use total_float_wrap::TotalNumber;
+///
+/// let mut numbers: Vec<TotalNumber> = vec![0.0_f64.into(), (-0.0_f32).into(), 0.into(), 1.5_f32.into()];
+/// numbers.sort();
+///
+/// assert_eq!(numbers, [0.into(), (-0.0_f32).into(), 0.0_f64.into(), 1.5_f32.into()]);
+/// ```
+///
+/// As a consequence values are only equal if they are of the same variant, and the `Hash`
+/// implementation hashes the variant along with the value.
+#[derive(Debug, Copy, Clone)]
+pub enum TotalNumber {
+    Int(i128),
+    F32(TotalF32),
+    F64(TotalF64),
+}
+
+impl TotalNumber {
+    /// The position of the variant in the tie-break between equal values
+    fn rank(&self) -> u8 {
+        match self {
+            TotalNumber::Int(_) => 0,
+            TotalNumber::F32(_) => 1,
+            TotalNumber::F64(_) => 2,
+        }
+    }
+
+    /// Compares the mathematical values, ignoring the variant
+    fn cmp_value(&self, other: &Self) -> Ordering {
+        use TotalNumber::*;
+
+        match (*self, *other) {
+            (Int(a), Int(b)) => a.cmp(&b),
+            (Int(a), F32(f)) => f.cmp_int(a).reverse(),
+            (Int(a), F64(f)) => f.cmp_int(a).reverse(),
+            (F32(f), Int(b)) => f.cmp_int(b),
+            (F64(f), Int(b)) => f.cmp_int(b),
+            (F32(a), F32(b)) => a.cmp(&b),
+            (F32(a), F64(b)) => TotalF64::from(a).cmp(&b),
+            (F64(a), F32(b)) => a.cmp(&TotalF64::from(b)),
+            (F64(a), F64(b)) => a.cmp(&b),
+        }
+    }
+}
+
+impl Default for TotalNumber {
+    fn default() -> Self {
+        TotalNumber::Int(0)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($int:ty),*) => {$(
+        impl From<$int> for TotalNumber {
+            fn from(i: $int) -> Self {
+                TotalNumber::Int(i.into())
+            }
+        }
+    )*};
+}
+
+impl_from_int!(i8, i16, i32, i64, i128, u8, u16, u32, u64);
+
+impl From<f32> for TotalNumber {
+    fn from(f: f32) -> Self {
+        TotalNumber::F32(TotalF32(f))
+    }
+}
+
+impl From<f64> for TotalNumber {
+    fn from(f: f64) -> Self {
+        TotalNumber::F64(TotalF64(f))
+    }
+}
+
+impl From<TotalF32> for TotalNumber {
+    fn from(f: TotalF32) -> Self {
+        TotalNumber::F32(f)
+    }
+}
+
+impl From<TotalF64> for TotalNumber {
+    fn from(f: TotalF64) -> Self {
+        TotalNumber::F64(f)
+    }
+}
+
+impl PartialEq for TotalNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for TotalNumber {}
+
+impl PartialOrd for TotalNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TotalNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_value(other)
+            .then_with(|| self.rank().cmp(&other.rank()))
+    }
+}
+
+impl Hash for TotalNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // values are only equal when they share a variant and the variant's own equality holds,
+        // so hashing both keeps k1 == k2 -> hash(k1) == hash(k2)
+        self.rank().hash(state);
+        match self {
+            TotalNumber::Int(i) => i.hash(state),
+            TotalNumber::F32(f) => f.hash(state),
+            TotalNumber::F64(f) => f.hash(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_total_number_cmp_across_variants() {
+        let cases: [(TotalNumber, TotalNumber); 8] = [
+            (i64::MAX.into(), 1e19_f64.into()),
+            (u64::MAX.into(), (u64::MAX as f64).into()),
+            (16777217.into(), 16777217.0_f64.into()),
+            (16777216.0_f32.into(), 16777217.into()),
+            (0.1_f64.into(), 0.1_f32.into()),
+            ((-f64::NAN).into(), i128::MIN.into()),
+            (i128::MAX.into(), f32::INFINITY.into()),
+            (f64::INFINITY.into(), f32::NAN.into()),
+        ];
+
+        for (a, b) in cases {
+            assert_eq!(Ordering::Less, a.cmp(&b), "{a:?} < {b:?}");
+            assert_eq!(Ordering::Greater, b.cmp(&a), "{b:?} > {a:?}");
+        }
+    }
+
+    #[test]
+    fn test_total_number_tie_break() {
+        let mut numbers: Vec<TotalNumber> = vec![
+            1.0_f64.into(),
+            0.0_f64.into(),
+            1.into(),
+            (-0.0_f64).into(),
+            0.0_f32.into(),
+            1.0_f32.into(),
+            0.into(),
+            (-0.0_f32).into(),
+        ];
+        numbers.sort();
+
+        let expected: [TotalNumber; 8] = [
+            0.into(),
+            (-0.0_f32).into(),
+            (-0.0_f64).into(),
+            0.0_f32.into(),
+            0.0_f64.into(),
+            1.into(),
+            1.0_f32.into(),
+            1.0_f64.into(),
+        ];
+        assert_eq!(numbers, expected);
+        assert_ne!(TotalNumber::from(2), TotalNumber::from(2.0));
+    }
+
+    #[test]
+    fn test_total_number_map_keys() {
+        let mut counts: HashMap<TotalNumber, u32> = HashMap::new();
+        for n in [1.into(), 1.0_f64.into(), 1.into(), 1.0_f32.into()] {
+            *counts.entry(n).or_default() += 1;
+        }
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&TotalNumber::from(1_u8)], 2);
+
+        let sorted: BTreeMap<TotalNumber, ()> = [2.5.into(), 2.into(), (-1).into()]
+            .into_iter()
+            .map(|n| (n, ()))
+            .collect();
+        let keys: Vec<_> = sorted.into_keys().collect();
+        assert_eq!(keys, [(-1).into(), 2.into(), 2.5.into()]);
+    }
+}